@@ -1274,26 +1274,15 @@ fn receiving_large_statement_from_one_sends_to_another_and_to_candidate_backing(
 			}
 		);
 
-		assert_matches!(
-			handle.recv().await,
-			AllMessages::NetworkBridgeTx(
-				NetworkBridgeTxMessage::ReportPeer(p, r)
-			) if p == peer_a && r == COST_FETCH_FAIL => {}
-		);
-
-		assert_matches!(
-			handle.recv().await,
-			AllMessages::NetworkBridgeTx(
-				NetworkBridgeTxMessage::ReportPeer(p, r)
-			) if p == peer_c && r == BENEFIT_VALID_RESPONSE => {}
-		);
-
-		assert_matches!(
-			handle.recv().await,
-			AllMessages::NetworkBridgeTx(
-				NetworkBridgeTxMessage::ReportPeer(p, r)
-			) if p == peer_a && r == BENEFIT_VALID_STATEMENT_FIRST => {}
-		);
+		expect_peer_reports(
+			&mut handle,
+			&[
+				(peer_a, COST_FETCH_FAIL),
+				(peer_c, BENEFIT_VALID_RESPONSE),
+				(peer_a, BENEFIT_VALID_STATEMENT_FIRST),
+			],
+		)
+		.await;
 
 		assert_matches!(
 			handle.recv().await,
@@ -2342,3 +2331,25 @@ fn derive_metadata_assuming_seconded(
 		signature: statement.unchecked_signature().clone(),
 	}
 }
+
+/// Receives exactly `expected.len()` messages, asserting that they are `ReportPeer` messages
+/// matching `expected` in order.
+async fn expect_peer_reports(
+	handle: &mut polkadot_node_subsystem_test_helpers::TestSubsystemContextHandle<
+		StatementDistributionMessage,
+	>,
+	expected: &[(PeerId, Rep)],
+) {
+	let mut reports = Vec::with_capacity(expected.len());
+	for _ in expected {
+		match handle.recv().await {
+			AllMessages::NetworkBridgeTx(NetworkBridgeTxMessage::ReportPeer(p, r)) =>
+				reports.push((p, r)),
+			other => panic!(
+				"Expected peer reports {:?}, got {:?} after reports {:?}",
+				expected, other, reports,
+			),
+		}
+	}
+	assert_eq!(reports, expected, "Unexpected sequence of peer reports");
+}
@@ -20,19 +20,23 @@ use frame_support::{
 	ensure,
 	traits::{Contains, Get},
 };
+use parity_scale_codec::DecodeLimit;
 use polkadot_parachain::primitives::IsSystem;
-use sp_std::{cell::Cell, marker::PhantomData, ops::ControlFlow, result::Result};
+use sp_std::{
+	cell::Cell, marker::PhantomData, ops::ControlFlow, result::Result, time::Duration, vec::Vec,
+};
 use xcm::{
 	latest::{
 		Instruction::{self, *},
 		InteriorMultiLocation, Junction, Junctions,
 		Junctions::X1,
-		MultiLocation, Weight,
+		MultiAssetFilter, MultiLocation, QueryId, Response, Weight,
 		WeightLimit::*,
+		Xcm, XcmContext, XcmHash,
 	},
-	CreateMatcher, MatchXcm,
+	CreateMatcher, MatchXcm, MAX_XCM_DECODE_DEPTH,
 };
-use xcm_executor::traits::{OnResponse, ShouldExecute};
+use xcm_executor::traits::{walk_instructions, OnResponse, ShouldExecute};
 
 /// Execution barrier that just takes `max_weight` from `weight_credit`.
 ///
@@ -173,105 +177,259 @@ impl<
 			"WithComputedOrigin origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
 			origin, instructions, max_weight, weight_credit,
 		);
-		let mut actual_origin = *origin;
-		let skipped = Cell::new(0usize);
-		// NOTE: We do not check the validity of `UniversalOrigin` here, meaning that a malicious
-		// origin could place a `UniversalOrigin` in order to spoof some location which gets free
-		// execution. This technical could get it past the barrier condition, but the execution
-		// would instantly fail since the first instruction would cause an error with the
-		// invalid UniversalOrigin.
-		instructions.matcher().match_next_inst_while(
-			|_| skipped.get() < MaxPrefixes::get() as usize,
-			|inst| {
-				match inst {
-					UniversalOrigin(new_global) => {
-						// Note the origin is *relative to local consensus*! So we need to escape
-						// local consensus with the `parents` before diving in into the
-						// `universal_location`.
-						actual_origin = X1(*new_global).relative_to(&LocalUniversal::get());
-					},
-					DescendOrigin(j) => {
-						let Ok(_) = actual_origin.append_with(*j) else { return Err(()) };
-					},
-					_ => return Ok(ControlFlow::Break(())),
-				};
-				skipped.set(skipped.get() + 1);
-				Ok(ControlFlow::Continue(()))
-			},
-		)?;
+		let (actual_origin, skipped) =
+			compute_origin(origin, instructions, &LocalUniversal::get(), MaxPrefixes::get())?;
 		InnerBarrier::should_execute(
 			&actual_origin,
-			&mut instructions[skipped.get()..],
+			&mut instructions[skipped..],
+			max_weight,
+			weight_credit,
+		)
+	}
+
+	fn should_execute_with_context<Call>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<Call>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"WithComputedOrigin origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, context: {:?}",
+			origin, instructions, max_weight, weight_credit, context,
+		);
+		let (actual_origin, skipped) =
+			compute_origin(origin, instructions, &LocalUniversal::get(), MaxPrefixes::get())?;
+		InnerBarrier::should_execute_with_context(
+			&actual_origin,
+			&mut instructions[skipped..],
 			max_weight,
 			weight_credit,
+			context,
 		)
 	}
 }
 
-/// Allows execution from any origin that is contained in `T` (i.e. `T::Contains(origin)`).
+/// Applies the origin-altering instructions among the first `max_prefixes` instructions of a
+/// message to `origin`, returning the computed origin and the number of instructions applied.
+fn compute_origin<Call>(
+	origin: &MultiLocation,
+	instructions: &mut [Instruction<Call>],
+	local_universal: &InteriorMultiLocation,
+	max_prefixes: u32,
+) -> Result<(MultiLocation, usize), ()> {
+	let mut actual_origin = *origin;
+	let skipped = Cell::new(0usize);
+	// NOTE: We do not check the validity of `UniversalOrigin` here, meaning that a malicious
+	// origin could place a `UniversalOrigin` in order to spoof some location which gets free
+	// execution. This technical could get it past the barrier condition, but the execution
+	// would instantly fail since the first instruction would cause an error with the
+	// invalid UniversalOrigin.
+	instructions.matcher().match_next_inst_while(
+		|_| skipped.get() < max_prefixes as usize,
+		|inst| {
+			match inst {
+				UniversalOrigin(new_global) => {
+					// Note the origin is *relative to local consensus*! So we need to escape
+					// local consensus with the `parents` before diving in into the
+					// `universal_location`.
+					actual_origin = X1(*new_global).relative_to(local_universal);
+				},
+				DescendOrigin(j) => {
+					let Ok(_) = actual_origin.append_with(*j) else { return Err(()) };
+				},
+				_ => return Ok(ControlFlow::Break(())),
+			};
+			skipped.set(skipped.get() + 1);
+			Ok(ControlFlow::Continue(()))
+		},
+	)?;
+	Ok((actual_origin, skipped.get()))
+}
+
+/// A derivative barrier, which counts the junctions descended into by the `DescendOrigin`
+/// instructions among the leading origin-altering instructions (`UniversalOrigin` and
+/// `DescendOrigin`) of the message and rejects it if there are more than `N` of them. Otherwise it
+/// evaluates `should_execute` of the `InnerBarrier` over the whole, unmodified message.
 ///
-/// Use only for executions from completely trusted origins, from which no unpermissioned messages
-/// can be sent.
-pub struct AllowUnpaidExecutionFrom<T>(PhantomData<T>);
-impl<T: Contains<MultiLocation>> ShouldExecute for AllowUnpaidExecutionFrom<T> {
-	fn should_execute<RuntimeCall>(
+/// This allows a chain to bound how deep a router may descend its origin independently of the
+/// `MaxPrefixes` given to `WithComputedOrigin`, which would typically be the `InnerBarrier`.
+pub struct AllowDescendOriginUpTo<InnerBarrier, const N: u8>(PhantomData<InnerBarrier>);
+impl<InnerBarrier, const N: u8> AllowDescendOriginUpTo<InnerBarrier, N> {
+	fn descends<Call>(instructions: &[Instruction<Call>]) -> usize {
+		instructions
+			.iter()
+			.take_while(|inst| matches!(inst, UniversalOrigin(..) | DescendOrigin(..)))
+			.map(|inst| match inst {
+				DescendOrigin(interior) => interior.len(),
+				_ => 0,
+			})
+			.sum()
+	}
+}
+impl<InnerBarrier: ShouldExecute, const N: u8> ShouldExecute
+	for AllowDescendOriginUpTo<InnerBarrier, N>
+{
+	fn should_execute<Call>(
 		origin: &MultiLocation,
-		instructions: &mut [Instruction<RuntimeCall>],
+		instructions: &mut [Instruction<Call>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowDescendOriginUpTo origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, max_weight, weight_credit,
+		);
+		ensure!(Self::descends(instructions) <= N as usize, ());
+		InnerBarrier::should_execute(origin, instructions, max_weight, weight_credit)
+	}
+
+	fn should_execute_with_context<Call>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<Call>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowDescendOriginUpTo origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, context: {:?}",
+			origin, instructions, max_weight, weight_credit, context,
+		);
+		ensure!(Self::descends(instructions) <= N as usize, ());
+		InnerBarrier::should_execute_with_context(
+			origin,
+			instructions,
+			max_weight,
+			weight_credit,
+			context,
+		)
+	}
+}
+
+/// Rejects any message whose effective origin is not `Prefix` or interior to it. The effective
+/// origin is computed from `origin` and the message's leading `UniversalOrigin` and
+/// `DescendOrigin` instructions exactly as `WithComputedOrigin` computes it, with the same
+/// `LocalUniversal` and `MaxPrefixes`.
+///
+/// Filter barrier; see `AllOf`.
+pub struct AllowOriginsUnder<Prefix, LocalUniversal, MaxPrefixes>(
+	PhantomData<(Prefix, LocalUniversal, MaxPrefixes)>,
+);
+impl<
+		Prefix: Get<MultiLocation>,
+		LocalUniversal: Get<InteriorMultiLocation>,
+		MaxPrefixes: Get<u32>,
+	> ShouldExecute for AllowOriginsUnder<Prefix, LocalUniversal, MaxPrefixes>
+{
+	fn should_execute<Call>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<Call>],
 		_max_weight: Weight,
 		_weight_credit: &mut Weight,
 	) -> Result<(), ()> {
 		log::trace!(
 			target: "xcm::barriers",
-			"AllowUnpaidExecutionFrom origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			"AllowOriginsUnder origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
 			origin, instructions, _max_weight, _weight_credit,
 		);
-		ensure!(T::contains(origin), ());
+		let (effective, _) =
+			compute_origin(origin, instructions, &LocalUniversal::get(), MaxPrefixes::get())?;
+		ensure!(effective.starts_with(&Prefix::get()), ());
 		Ok(())
 	}
 }
 
-/// Allows execution from any origin that is contained in `T` (i.e. `T::Contains(origin)`) if the
-/// message begins with the instruction `UnpaidExecution`.
+/// Type which can match on some kinds of XCM instruction.
+pub trait MatchesInstruction {
+	/// Returns `true` if `instruction` is of a kind matched by this type.
+	fn matches<Call>(instruction: &Instruction<Call>) -> bool;
+}
+
+/// Declares types implementing `MatchesInstruction` for a set of instruction variants.
 ///
-/// Use only for executions from trusted origin groups.
-pub struct AllowExplicitUnpaidExecutionFrom<T>(PhantomData<T>);
-impl<T: Contains<MultiLocation>> ShouldExecute for AllowExplicitUnpaidExecutionFrom<T> {
-	fn should_execute<Call>(
+/// E.g.
+/// ```nocompile
+/// match_instructions! {
+/// 	pub type TransactOrErrorHandler = { Transact, SetErrorHandler };
+/// }
+/// type MyBarrier =
+/// 	AllOf<(DenyInstructions<TransactOrErrorHandler>, AllowUnpaidExecutionFrom<Trusted>)>;
+/// ```
+#[macro_export]
+macro_rules! match_instructions {
+	( $( $(#[$attr:meta])* $vis:vis type $n:ident = { $( $variant:ident ),+ $(,)? }; )* ) => {
+		$(
+			$(#[$attr])*
+			$vis struct $n;
+			impl $crate::MatchesInstruction for $n {
+				fn matches<Call>(instruction: &$crate::__private::Instruction<Call>) -> bool {
+					matches!(
+						instruction,
+						$( $crate::__private::Instruction::$variant { .. } )|+
+					)
+				}
+			}
+		)*
+	};
+}
+
+/// Rejects any message containing an instruction matched by `Deny`, including instructions
+/// within the nested programs of `SetAppendix` and `SetErrorHandler`.
+///
+/// Filter barrier; see `AllOf`.
+pub struct DenyInstructions<Deny>(PhantomData<Deny>);
+impl<Deny: MatchesInstruction> ShouldExecute for DenyInstructions<Deny> {
+	fn should_execute<RuntimeCall>(
 		origin: &MultiLocation,
-		instructions: &mut [Instruction<Call>],
-		max_weight: Weight,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
 		_weight_credit: &mut Weight,
 	) -> Result<(), ()> {
 		log::trace!(
 			target: "xcm::barriers",
-			"AllowExplicitUnpaidExecutionFrom origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
-			origin, instructions, max_weight, _weight_credit,
+			"DenyInstructions origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
 		);
-		ensure!(T::contains(origin), ());
-		instructions.matcher().match_next_inst(|inst| match inst {
-			UnpaidExecution { weight_limit: Limited(m), .. } if m.all_gte(max_weight) => Ok(()),
-			UnpaidExecution { weight_limit: Unlimited, .. } => Ok(()),
-			_ => Err(()),
-		})?;
+		let walked = walk_instructions(instructions, &mut |inst| {
+			if Deny::matches(inst) {
+				ControlFlow::Break(())
+			} else {
+				ControlFlow::Continue(())
+			}
+		});
+		ensure!(walked.is_continue(), ());
 		Ok(())
 	}
 }
 
-/// Allows a message only if it is from a system-level child parachain.
-pub struct IsChildSystemParachain<ParaId>(PhantomData<ParaId>);
-impl<ParaId: IsSystem + From<u32>> Contains<MultiLocation> for IsChildSystemParachain<ParaId> {
-	fn contains(l: &MultiLocation) -> bool {
-		matches!(
-			l.interior(),
-			Junctions::X1(Junction::Parachain(id))
-				if ParaId::from(*id).is_system() && l.parent_count() == 0,
-		)
-	}
+/// Storage of the number of messages counted by `RateLimitBarrier`.
+pub trait RateLimitStore {
+	/// The current block number.
+	fn current_block() -> u32;
+	/// The block in which messages from `origin` were last counted, and how many were counted.
+	fn get(origin: &MultiLocation) -> Option<(u32, u32)>;
+	/// Record that `count` messages from `origin` were counted in `block`.
+	fn set(origin: &MultiLocation, block: u32, count: u32);
 }
 
-/// Allows only messages if the generic `ResponseHandler` expects them via `expecting_response`.
-pub struct AllowKnownQueryResponses<ResponseHandler>(PhantomData<ResponseHandler>);
-impl<ResponseHandler: OnResponse> ShouldExecute for AllowKnownQueryResponses<ResponseHandler> {
+/// Rejects any message once `PER_BLOCK` messages from the same origin have passed this barrier
+/// within the current block.
+///
+/// Origins within a parachain are counted against the parachain, so that an origin cannot get
+/// around the limit by descending into different locations of the same parachain.
+///
+/// A message is counted as soon as it passes this barrier, even if another barrier then rejects
+/// it, e.g. a later element of the same `AllOf`. Put this barrier last within `AllOf` so that
+/// only messages which pass every other barrier use up the origin's limit.
+///
+/// Filter barrier; see `AllOf`.
+pub struct RateLimitBarrier<Store, const PER_BLOCK: u32>(PhantomData<Store>);
+impl<Store: RateLimitStore, const PER_BLOCK: u32> ShouldExecute
+	for RateLimitBarrier<Store, PER_BLOCK>
+{
 	fn should_execute<RuntimeCall>(
 		origin: &MultiLocation,
 		instructions: &mut [Instruction<RuntimeCall>],
@@ -280,26 +438,148 @@ impl<ResponseHandler: OnResponse> ShouldExecute for AllowKnownQueryResponses<Res
 	) -> Result<(), ()> {
 		log::trace!(
 			target: "xcm::barriers",
-			"AllowKnownQueryResponses origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			"RateLimitBarrier origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
 			origin, instructions, _max_weight, _weight_credit,
 		);
-		instructions
-			.matcher()
-			.assert_remaining_insts(1)?
-			.match_next_inst(|inst| match inst {
-				QueryResponse { query_id, querier, .. }
-					if ResponseHandler::expecting_response(origin, *query_id, querier.as_ref()) =>
-					Ok(()),
-				_ => Err(()),
-			})?;
+		let key = match origin.interior().iter().position(|j| matches!(j, Junction::Parachain(_))) {
+			Some(index) => {
+				let mut key = MultiLocation::new(origin.parents, Junctions::Here);
+				for junction in origin.interior().iter().take(index + 1) {
+					let _ = key.push_interior(*junction);
+				}
+				key
+			},
+			None => *origin,
+		};
+		let block = Store::current_block();
+		let count = match Store::get(&key) {
+			Some((counted_in, count)) if counted_in == block => count,
+			_ => 0,
+		};
+		ensure!(count < PER_BLOCK, ());
+		Store::set(&key, block, count + 1);
 		Ok(())
 	}
 }
 
-/// Allows execution from `origin` if it is just a straight `SubscribeVersion` or
-/// `UnsubscribeVersion` instruction.
-pub struct AllowSubscriptionsFrom<T>(PhantomData<T>);
-impl<T: Contains<MultiLocation>> ShouldExecute for AllowSubscriptionsFrom<T> {
+/// Sink for the rejections of a barrier wrapped in `WithMetrics`.
+pub trait BarrierMetrics {
+	/// Called each time the barrier rejects a message from `origin`.
+	fn on_reject(origin: &MultiLocation);
+}
+
+impl BarrierMetrics for () {
+	fn on_reject(_origin: &MultiLocation) {}
+}
+
+/// A derivative barrier which evaluates `InnerBarrier` and reports each rejection to `Metrics`.
+pub struct WithMetrics<InnerBarrier, Metrics>(PhantomData<(InnerBarrier, Metrics)>);
+impl<InnerBarrier: ShouldExecute, Metrics: BarrierMetrics> ShouldExecute
+	for WithMetrics<InnerBarrier, Metrics>
+{
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		InnerBarrier::should_execute(origin, instructions, max_weight, weight_credit)
+			.map_err(|()| Metrics::on_reject(origin))
+	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		InnerBarrier::should_execute_with_context(
+			origin,
+			instructions,
+			max_weight,
+			weight_credit,
+			context,
+		)
+		.map_err(|()| Metrics::on_reject(origin))
+	}
+}
+
+/// Type which receives the duration of each barrier evaluation measured by `Timed`.
+pub trait BarrierTimingSink {
+	/// Whether evaluations should be timed at all. Sinks which ignore their samples should set this
+	/// to `false` so that no clock is read.
+	const ENABLED: bool = true;
+
+	/// Called with the duration of each barrier evaluation.
+	fn record(duration: Duration);
+}
+
+impl BarrierTimingSink for () {
+	const ENABLED: bool = false;
+	fn record(_duration: Duration) {}
+}
+
+/// A derivative barrier which evaluates `InnerBarrier` and reports how long it took to `Sink`,
+/// leaving the result unchanged.
+///
+/// Only available with the `std` feature since it needs a clock.
+#[cfg(feature = "std")]
+pub struct Timed<InnerBarrier, Sink>(PhantomData<(InnerBarrier, Sink)>);
+#[cfg(feature = "std")]
+impl<InnerBarrier: ShouldExecute, Sink: BarrierTimingSink> ShouldExecute
+	for Timed<InnerBarrier, Sink>
+{
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		if !Sink::ENABLED {
+			return InnerBarrier::should_execute(origin, instructions, max_weight, weight_credit)
+		}
+		let start = std::time::Instant::now();
+		let result = InnerBarrier::should_execute(origin, instructions, max_weight, weight_credit);
+		Sink::record(start.elapsed());
+		result
+	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		if !Sink::ENABLED {
+			return InnerBarrier::should_execute_with_context(
+				origin,
+				instructions,
+				max_weight,
+				weight_credit,
+				context,
+			)
+		}
+		let start = std::time::Instant::now();
+		let result = InnerBarrier::should_execute_with_context(
+			origin,
+			instructions,
+			max_weight,
+			weight_credit,
+			context,
+		);
+		Sink::record(start.elapsed());
+		result
+	}
+}
+
+/// Rejects any message with more than `MAX` top-level instructions. Nested programs, e.g. within
+/// `SetAppendix`, are not counted.
+///
+/// Filter barrier; see `AllOf`.
+pub struct LimitInstructionCount<const MAX: u32>;
+impl<const MAX: u32> ShouldExecute for LimitInstructionCount<MAX> {
 	fn should_execute<RuntimeCall>(
 		origin: &MultiLocation,
 		instructions: &mut [Instruction<RuntimeCall>],
@@ -308,17 +588,1022 @@ impl<T: Contains<MultiLocation>> ShouldExecute for AllowSubscriptionsFrom<T> {
 	) -> Result<(), ()> {
 		log::trace!(
 			target: "xcm::barriers",
-			"AllowSubscriptionsFrom origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			"LimitInstructionCount origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
 			origin, instructions, _max_weight, _weight_credit,
 		);
-		ensure!(T::contains(origin), ());
-		instructions
-			.matcher()
-			.assert_remaining_insts(1)?
-			.match_next_inst(|inst| match inst {
-				SubscribeVersion { .. } | UnsubscribeVersion => Ok(()),
-				_ => Err(()),
-			})?;
+		ensure!(instructions.len() <= MAX as usize, ());
+		Ok(())
+	}
+}
+
+/// A set of barriers which must all pass a message. Implemented for tuples of `ShouldExecute`.
+pub trait ShouldExecuteAll {
+	/// Returns `Ok` if every barrier of the set passes the message, evaluating them in order and
+	/// stopping at the first to reject it.
+	fn should_execute_all<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()>;
+
+	/// As `should_execute_all`, but evaluating each barrier with `context`.
+	fn should_execute_all_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()>;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+#[tuple_types_custom_trait_bound(ShouldExecute)]
+impl ShouldExecuteAll for Tuple {
+	fn should_execute_all<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		for_tuples!( #(
+			Tuple::should_execute(origin, instructions, max_weight, weight_credit)?;
+		)* );
+		Ok(())
+	}
+
+	fn should_execute_all_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		for_tuples!( #(
+			Tuple::should_execute_with_context(
+				origin, instructions, max_weight, weight_credit, context,
+			)?;
+		)* );
 		Ok(())
 	}
 }
+
+/// A derivative barrier which passes a message only if every barrier of the tuple `Barriers`
+/// passes it, in contrast to a plain barrier tuple of which only one needs to.
+///
+/// This is how filter barriers are meant to be used. An allow barrier, e.g.
+/// `AllowTopLevelPaidExecutionFrom`, passes only the messages it positively allows. A filter
+/// barrier, e.g. `DenyInstructions`, instead passes every message which it does not object to. A
+/// filter must therefore never be an element of a plain barrier tuple, where the first barrier to
+/// pass a message wins and the filter would let everything else through. Combine filters with the
+/// allow barriers using `AllOf` instead.
+///
+/// Any weight credit taken by the barriers is given back if the message is rejected.
+///
+/// E.g.
+/// ```nocompile
+/// type MyBarrier = (
+/// 	TakeWeightCredit,
+/// 	AllOf<(
+/// 		DenyInstructions<TransactOrErrorHandler>,
+/// 		AllowTopLevelPaidExecutionFrom<SiblingLocations>,
+/// 	)>,
+/// );
+/// ```
+pub struct AllOf<Barriers>(PhantomData<Barriers>);
+impl<Barriers: ShouldExecuteAll> ShouldExecute for AllOf<Barriers> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllOf origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, max_weight, weight_credit,
+		);
+		let initial_credit = *weight_credit;
+		Barriers::should_execute_all(origin, instructions, max_weight, weight_credit)
+			.map_err(|()| *weight_credit = initial_credit)
+	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllOf origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, context: {:?}",
+			origin, instructions, max_weight, weight_credit, context,
+		);
+		let initial_credit = *weight_credit;
+		Barriers::should_execute_all_with_context(
+			origin,
+			instructions,
+			max_weight,
+			weight_credit,
+			context,
+		)
+		.map_err(|()| *weight_credit = initial_credit)
+	}
+}
+
+/// Rejects any message which does not declare a weight limit in `BuyExecution` or
+/// `UnpaidExecution`, or whose declared limit is below `max_weight`, the weight of the message as
+/// determined by the executor's `Weigher`. An `Unlimited` weight limit always passes.
+///
+/// Filter barrier; see `AllOf`.
+pub struct EnforceWeightLimit;
+impl ShouldExecute for EnforceWeightLimit {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"EnforceWeightLimit origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, max_weight, _weight_credit,
+		);
+		let weight_limit = instructions
+			.iter()
+			.find_map(|inst| match inst {
+				BuyExecution { weight_limit, .. } | UnpaidExecution { weight_limit, .. } =>
+					Some(weight_limit),
+				_ => None,
+			})
+			.ok_or(())?;
+		match weight_limit {
+			Limited(weight) => ensure!(weight.all_gte(max_weight), ()),
+			Unlimited => (),
+		}
+		Ok(())
+	}
+}
+
+/// A derivative barrier which evaluates `InnerBarrier` and logs each rejection under the log target
+/// given by `Target` rather than the default `xcm::should_execute`.
+///
+/// Useful for telling apart the barriers of multiple XCM configurations, e.g. for bridges.
+pub struct WithLogTarget<InnerBarrier, Target>(PhantomData<(InnerBarrier, Target)>);
+impl<InnerBarrier: ShouldExecute, Target: Get<&'static str>> ShouldExecute
+	for WithLogTarget<InnerBarrier, Target>
+{
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		let result = InnerBarrier::should_execute(origin, instructions, max_weight, weight_credit);
+		if result.is_err() {
+			log::trace!(
+				target: Target::get(),
+				"did not pass barrier: origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+				origin, instructions, max_weight, weight_credit,
+			);
+		}
+		result
+	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		let result = InnerBarrier::should_execute_with_context(
+			origin,
+			instructions,
+			max_weight,
+			weight_credit,
+			context,
+		);
+		if result.is_err() {
+			log::trace!(
+				target: Target::get(),
+				"did not pass barrier: origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, context: {:?}",
+				origin, instructions, max_weight, weight_credit, context,
+			);
+		}
+		result
+	}
+}
+
+/// Rejects any message with more than `MAX` `Transact` instructions, counting those within nested
+/// programs too.
+///
+/// Filter barrier; see `AllOf`.
+pub struct LimitTransacts<const MAX: u32>;
+impl<const MAX: u32> ShouldExecute for LimitTransacts<MAX> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"LimitTransacts origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let mut transacts = 0u32;
+		let walked = walk_instructions(instructions, &mut |inst| {
+			if matches!(inst, Transact { .. }) {
+				transacts = transacts.saturating_add(1);
+				if transacts > MAX {
+					return ControlFlow::Break(())
+				}
+			}
+			ControlFlow::Continue(())
+		});
+		ensure!(walked.is_continue(), ());
+		Ok(())
+	}
+}
+
+/// Policy on which origins may send which instructions.
+///
+/// Can be amalgamated into a tuple, in which case an instruction is allowed only if every element
+/// allows it.
+pub trait InstructionOriginPolicy {
+	/// Returns `true` if `origin` may send `instruction`.
+	fn allows<Call>(origin: &MultiLocation, instruction: &Instruction<Call>) -> bool;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl InstructionOriginPolicy for Tuple {
+	fn allows<Call>(origin: &MultiLocation, instruction: &Instruction<Call>) -> bool {
+		for_tuples!( #(
+			if !Tuple::allows(origin, instruction) {
+				return false;
+			}
+		)* );
+		true
+	}
+}
+
+/// Policy allowing instructions matched by `Instructions` only from origins contained in `Origins`,
+/// and any other instruction from any origin.
+pub struct RestrictInstructionsTo<Instructions, Origins>(PhantomData<(Instructions, Origins)>);
+impl<Instructions: MatchesInstruction, Origins: Contains<MultiLocation>> InstructionOriginPolicy
+	for RestrictInstructionsTo<Instructions, Origins>
+{
+	fn allows<Call>(origin: &MultiLocation, instruction: &Instruction<Call>) -> bool {
+		!Instructions::matches(instruction) || Origins::contains(origin)
+	}
+}
+
+/// Rejects any message from `origin` containing an instruction, including within nested programs,
+/// which `Policy` does not allow from `origin`.
+///
+/// The policy is checked against the origin given to the barrier, so wrap it in
+/// `WithComputedOrigin` for it to apply to the computed origin instead.
+///
+/// E.g.
+/// ```nocompile
+/// type MyPolicy = (
+/// 	RestrictInstructionsTo<TransactOnly, ParentLocation>,
+/// 	RestrictInstructionsTo<ReserveAssetDepositedOnly, SiblingLocations>,
+/// );
+/// type MyBarrier =
+/// 	AllOf<(PerInstructionOriginPolicy<MyPolicy>, AllowUnpaidExecutionFrom<Trusted>)>;
+/// ```
+///
+/// Filter barrier; see `AllOf`.
+pub struct PerInstructionOriginPolicy<Policy>(PhantomData<Policy>);
+impl<Policy: InstructionOriginPolicy> ShouldExecute for PerInstructionOriginPolicy<Policy> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"PerInstructionOriginPolicy origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let walked = walk_instructions(instructions, &mut |inst| {
+			if Policy::allows(origin, inst) {
+				ControlFlow::Continue(())
+			} else {
+				ControlFlow::Break(())
+			}
+		});
+		ensure!(walked.is_continue(), ());
+		Ok(())
+	}
+}
+
+/// Statistics on the XCMs executed so far in the current block.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub struct ExecutionStats {
+	/// The number of messages executed.
+	pub messages_executed: u32,
+	/// The total weight consumed by their execution.
+	pub weight_consumed: Weight,
+}
+
+/// Type which can provide the `ExecutionStats` of the current block, e.g. the pallet which
+/// processes incoming XCMs.
+pub trait ExecutionStatsProvider {
+	/// The statistics of the current block.
+	fn execution_stats() -> ExecutionStats;
+}
+
+/// A derivative barrier which evaluates `Relaxed` while the XCM execution in the current block is
+/// below capacity, and `Strict` once it reaches it: that is, once `MaxMessages` messages have been
+/// executed, or either component of the weight consumed reaches `Threshold`.
+///
+/// Useful for tightening the barrier as the block fills up, e.g. to stop accepting unpaid
+/// execution from less trusted origins.
+pub struct TightenNearCapacity<Relaxed, Strict, Stats, Threshold, MaxMessages>(
+	PhantomData<(Relaxed, Strict, Stats, Threshold, MaxMessages)>,
+);
+impl<
+		Relaxed,
+		Strict,
+		Stats: ExecutionStatsProvider,
+		Threshold: Get<Weight>,
+		MaxMessages: Get<u32>,
+	> TightenNearCapacity<Relaxed, Strict, Stats, Threshold, MaxMessages>
+{
+	fn at_capacity(stats: &ExecutionStats) -> bool {
+		stats.messages_executed >= MaxMessages::get() ||
+			stats.weight_consumed.any_gte(Threshold::get())
+	}
+}
+impl<
+		Relaxed: ShouldExecute,
+		Strict: ShouldExecute,
+		Stats: ExecutionStatsProvider,
+		Threshold: Get<Weight>,
+		MaxMessages: Get<u32>,
+	> ShouldExecute for TightenNearCapacity<Relaxed, Strict, Stats, Threshold, MaxMessages>
+{
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		let stats = Stats::execution_stats();
+		log::trace!(
+			target: "xcm::barriers",
+			"TightenNearCapacity origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, stats: {:?}",
+			origin, instructions, max_weight, weight_credit, stats,
+		);
+		if Self::at_capacity(&stats) {
+			Strict::should_execute(origin, instructions, max_weight, weight_credit)
+		} else {
+			Relaxed::should_execute(origin, instructions, max_weight, weight_credit)
+		}
+	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		let stats = Stats::execution_stats();
+		log::trace!(
+			target: "xcm::barriers",
+			"TightenNearCapacity origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, context: {:?}, stats: {:?}",
+			origin, instructions, max_weight, weight_credit, context, stats,
+		);
+		if Self::at_capacity(&stats) {
+			Strict::should_execute_with_context(
+				origin,
+				instructions,
+				max_weight,
+				weight_credit,
+				context,
+			)
+		} else {
+			Relaxed::should_execute_with_context(
+				origin,
+				instructions,
+				max_weight,
+				weight_credit,
+				context,
+			)
+		}
+	}
+}
+
+/// Rejects any message with an instruction carrying more than `MAX` assets, including within nested
+/// programs.
+///
+/// An asset filter carries as many assets as it lists, or for a counted wildcard, as many as it is
+/// limited to. Uncounted wildcards are not limited by this barrier.
+///
+/// Filter barrier; see `AllOf`.
+pub struct LimitAssetCount<const MAX: u32>;
+impl<const MAX: u32> ShouldExecute for LimitAssetCount<MAX> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"LimitAssetCount origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let too_many = |count: usize| count > MAX as usize;
+		let too_many_filtered =
+			|filter: &MultiAssetFilter| filter.count().map_or(false, |count| count > MAX);
+		let walked = walk_instructions(instructions, &mut |inst| {
+			let over_limit = match inst {
+				WithdrawAsset(assets) |
+				ReceiveTeleportedAsset(assets) |
+				ReserveAssetDeposited(assets) |
+				TransferAsset { assets, .. } |
+				TransferReserveAsset { assets, .. } |
+				ClaimAsset { assets, .. } |
+				BurnAsset(assets) |
+				ExpectAsset(assets) |
+				QueryResponse { response: Response::Assets(assets), .. } => too_many(assets.len()),
+				ReportHolding { assets, .. } |
+				DepositAsset { assets, .. } |
+				DepositReserveAsset { assets, .. } |
+				InitiateReserveWithdraw { assets, .. } |
+				InitiateTeleport { assets, .. } => too_many_filtered(assets),
+				ExchangeAsset { give, want, .. } => too_many_filtered(give) || too_many(want.len()),
+				_ => false,
+			};
+			if over_limit {
+				ControlFlow::Break(())
+			} else {
+				ControlFlow::Continue(())
+			}
+		});
+		ensure!(walked.is_continue(), ());
+		Ok(())
+	}
+}
+
+/// A derivative barrier which evaluates `InnerBarrier`, rejecting the message rather than unwinding
+/// if it panics.
+///
+/// Only available with the `std` feature since unwinding is not supported in `no_std`.
+///
+/// For off-chain tooling and tests only. It must never be part of a runtime's barrier: a native
+/// runtime would reject a message on which the Wasm runtime aborts, so nodes executing the block
+/// natively and in Wasm would disagree on its outcome.
+#[cfg(feature = "std")]
+pub struct CatchPanics<InnerBarrier>(PhantomData<InnerBarrier>);
+#[cfg(feature = "std")]
+impl<InnerBarrier: ShouldExecute> ShouldExecute for CatchPanics<InnerBarrier> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"CatchPanics origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, max_weight, weight_credit,
+		);
+		std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+			InnerBarrier::should_execute(
+				origin,
+				&mut *instructions,
+				max_weight,
+				&mut *weight_credit,
+			)
+		}))
+		.unwrap_or_else(|_| Self::on_panic(origin, max_weight))
+	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"CatchPanics origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, context: {:?}",
+			origin, instructions, max_weight, weight_credit, context,
+		);
+		std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+			InnerBarrier::should_execute_with_context(
+				origin,
+				&mut *instructions,
+				max_weight,
+				&mut *weight_credit,
+				context,
+			)
+		}))
+		.unwrap_or_else(|_| Self::on_panic(origin, max_weight))
+	}
+}
+#[cfg(feature = "std")]
+impl<InnerBarrier> CatchPanics<InnerBarrier> {
+	fn on_panic(origin: &MultiLocation, max_weight: Weight) -> Result<(), ()> {
+		log::error!(
+			target: "xcm::barriers",
+			"CatchPanics barrier panicked, rejecting message. origin: {:?}, max_weight: {:?}",
+			origin, max_weight,
+		);
+		Err(())
+	}
+}
+
+/// An expected ordering of the instructions at the top level of a message.
+pub trait InstructionOrder {
+	/// Returns the phase of the program to which `instruction` belongs, or `None` if it may appear
+	/// anywhere. Phases must not decrease along the program.
+	fn phase<Call>(instruction: &Instruction<Call>) -> Option<u8>;
+}
+
+/// The canonical instruction order: origin alterations, then asset loading, then paying for
+/// execution, then the body. `ClearOrigin`, `SetAppendix`, `SetErrorHandler`, `SetTopic` and
+/// `ClearTopic` may appear anywhere, e.g. the leading `SetAppendix` of `pallet_xcm::report_outcome`
+/// or the leading `SetTopic` required by `RequireSetTopic`.
+pub struct CanonicalInstructionOrder;
+impl InstructionOrder for CanonicalInstructionOrder {
+	fn phase<Call>(instruction: &Instruction<Call>) -> Option<u8> {
+		match instruction {
+			ClearOrigin | SetAppendix(..) | SetErrorHandler(..) | SetTopic(..) | ClearTopic => None,
+			UniversalOrigin(..) | DescendOrigin(..) | AliasOrigin(..) => Some(0),
+			WithdrawAsset(..) |
+			ReserveAssetDeposited(..) |
+			ReceiveTeleportedAsset(..) |
+			ClaimAsset { .. } => Some(1),
+			BuyExecution { .. } | UnpaidExecution { .. } => Some(2),
+			_ => Some(3),
+		}
+	}
+}
+
+/// Rejects any message whose top-level instructions do not follow the phases given by `Order`.
+///
+/// Filter barrier; see `AllOf`.
+pub struct EnforceInstructionOrder<Order>(PhantomData<Order>);
+impl<Order: InstructionOrder> ShouldExecute for EnforceInstructionOrder<Order> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"EnforceInstructionOrder origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let mut current = 0u8;
+		for (index, instruction) in instructions.iter().enumerate() {
+			if let Some(phase) = Order::phase(instruction) {
+				if phase < current {
+					log::debug!(
+						target: "xcm::barriers",
+						"EnforceInstructionOrder rejected instruction {} out of order: {:?}",
+						index, instruction,
+					);
+					return Err(())
+				}
+				current = phase;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Rejects any message whose weight, as computed by the executor's weigher, has a proof size
+/// component greater than `MAX_POV`, regardless of its ref time.
+///
+/// Filter barrier; see `AllOf`.
+pub struct LimitProofSize<const MAX_POV: u64>;
+impl<const MAX_POV: u64> ShouldExecute for LimitProofSize<MAX_POV> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"LimitProofSize origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, max_weight, _weight_credit,
+		);
+		ensure!(max_weight.proof_size() <= MAX_POV, ());
+		Ok(())
+	}
+}
+
+/// Rejects any message with programs nested more than `MAX` levels deep through `SetAppendix` and
+/// `SetErrorHandler`, the top-level program being at depth zero.
+///
+/// The depth is computed iteratively and stops as soon as `MAX` is exceeded, so this barrier is
+/// safe to evaluate before any barrier which walks the nested programs recursively.
+///
+/// Filter barrier; see `AllOf`.
+pub struct LimitNestingDepth<const MAX: u8>;
+impl<const MAX: u8> ShouldExecute for LimitNestingDepth<MAX> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"LimitNestingDepth origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let mut pending: Vec<(&[Instruction<RuntimeCall>], u8)> = sp_std::vec![(&*instructions, 0)];
+		while let Some((program, depth)) = pending.pop() {
+			for instruction in program {
+				if let SetAppendix(xcm) | SetErrorHandler(xcm) = instruction {
+					ensure!(depth < MAX, ());
+					pending.push((&xcm.0[..], depth + 1));
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Where in a message `RequireSetTopic` requires the `SetTopic` instruction to be.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum TopicPosition {
+	/// The first instruction of the message.
+	Leading,
+	/// The last instruction of the message.
+	Trailing,
+	/// Any top-level instruction of the message.
+	Anywhere,
+}
+
+/// Rejects any message without a top-level `SetTopic` instruction at the position given by
+/// `Position`, so that the effects of every accepted message can be traced by its topic.
+///
+/// Filter barrier; see `AllOf`.
+pub struct RequireSetTopic<Position>(PhantomData<Position>);
+impl<Position: Get<TopicPosition>> ShouldExecute for RequireSetTopic<Position> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"RequireSetTopic origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let is_topic = |inst: &Instruction<RuntimeCall>| matches!(inst, SetTopic(..));
+		let found = match Position::get() {
+			TopicPosition::Leading => instructions.first().map_or(false, is_topic),
+			TopicPosition::Trailing => instructions.last().map_or(false, is_topic),
+			TopicPosition::Anywhere => instructions.iter().any(is_topic),
+		};
+		ensure!(found, ());
+		Ok(())
+	}
+}
+
+/// Rejects any message which transfers assets, through `TransferReserveAsset`,
+/// `DepositReserveAsset`, `InitiateReserveWithdraw` or `InitiateTeleport`, to a destination not
+/// contained in `Whitelist`. Instructions within nested programs are checked too.
+///
+/// Filter barrier; see `AllOf`.
+pub struct AllowTransferDestinations<Whitelist>(PhantomData<Whitelist>);
+impl<Whitelist: Contains<MultiLocation>> ShouldExecute for AllowTransferDestinations<Whitelist> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowTransferDestinations origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let walked = walk_instructions(instructions, &mut |inst| match inst {
+			TransferReserveAsset { dest, .. } |
+			DepositReserveAsset { dest, .. } |
+			InitiateReserveWithdraw { reserve: dest, .. } |
+			InitiateTeleport { dest, .. }
+				if !Whitelist::contains(dest) =>
+				ControlFlow::Break(()),
+			_ => ControlFlow::Continue(()),
+		});
+		ensure!(walked.is_continue(), ());
+		Ok(())
+	}
+}
+
+/// Rejects any message with a `BuyExecution` instruction, including within nested programs, whose
+/// `Limited` weight limit has a ref time below `MIN_REFTIME` or a proof size below `MIN_POV`.
+/// `Unlimited` always passes.
+///
+/// Filter barrier; see `AllOf`.
+pub struct RequireMinimumBuyExecution<const MIN_REFTIME: u64, const MIN_POV: u64>;
+impl<const MIN_REFTIME: u64, const MIN_POV: u64> ShouldExecute
+	for RequireMinimumBuyExecution<MIN_REFTIME, MIN_POV>
+{
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"RequireMinimumBuyExecution origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let walked = walk_instructions(instructions, &mut |inst| match inst {
+			BuyExecution { weight_limit: Limited(limit), .. }
+				if limit.ref_time() < MIN_REFTIME || limit.proof_size() < MIN_POV =>
+				ControlFlow::Break(()),
+			_ => ControlFlow::Continue(()),
+		});
+		ensure!(walked.is_continue(), ());
+		Ok(())
+	}
+}
+
+/// Rejects any message in which a `DepositAsset` which may execute after a `RefundSurplus` deposits
+/// to a beneficiary other than the message's effective origin. The effective origin is computed
+/// from `origin` and the message's leading `UniversalOrigin` and `DescendOrigin` instructions
+/// exactly as `WithComputedOrigin` computes it, with the same `LocalUniversal` and `MaxPrefixes`.
+///
+/// Within a program, a `DepositAsset` may execute after a `RefundSurplus` if it follows it. Since
+/// programs nested within `SetAppendix` and `SetErrorHandler` execute after the program containing
+/// them, every `DepositAsset` in them is treated as following a `RefundSurplus` if that program
+/// contains one anywhere, including within its other nested programs.
+///
+/// Filter barrier; see `AllOf`.
+pub struct RequireSelfBeneficiaryRefund<LocalUniversal, MaxPrefixes>(
+	PhantomData<(LocalUniversal, MaxPrefixes)>,
+);
+impl<LocalUniversal, MaxPrefixes> RequireSelfBeneficiaryRefund<LocalUniversal, MaxPrefixes> {
+	fn refunds_only_to<Call>(
+		origin: &MultiLocation,
+		instructions: &[Instruction<Call>],
+		mut refunded: bool,
+	) -> bool {
+		let enclosing_refunded = refunded ||
+			walk_instructions(instructions, &mut |inst| match inst {
+				RefundSurplus => ControlFlow::Break(()),
+				_ => ControlFlow::Continue(()),
+			})
+			.is_break();
+		instructions.iter().all(|inst| match inst {
+			RefundSurplus => {
+				refunded = true;
+				true
+			},
+			DepositAsset { beneficiary, .. } => !refunded || beneficiary == origin,
+			SetAppendix(xcm) | SetErrorHandler(xcm) =>
+				Self::refunds_only_to(origin, &xcm.0, enclosing_refunded),
+			_ => true,
+		})
+	}
+}
+impl<LocalUniversal: Get<InteriorMultiLocation>, MaxPrefixes: Get<u32>> ShouldExecute
+	for RequireSelfBeneficiaryRefund<LocalUniversal, MaxPrefixes>
+{
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"RequireSelfBeneficiaryRefund origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let (effective, _) =
+			compute_origin(origin, instructions, &LocalUniversal::get(), MaxPrefixes::get())?;
+		ensure!(Self::refunds_only_to(&effective, instructions, false), ());
+		Ok(())
+	}
+}
+
+/// Allows execution from any origin that is contained in `T` (i.e. `T::Contains(origin)`).
+///
+/// Use only for executions from completely trusted origins, from which no unpermissioned messages
+/// can be sent.
+pub struct AllowUnpaidExecutionFrom<T>(PhantomData<T>);
+impl<T: Contains<MultiLocation>> ShouldExecute for AllowUnpaidExecutionFrom<T> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowUnpaidExecutionFrom origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		ensure!(T::contains(origin), ());
+		Ok(())
+	}
+}
+
+/// Allows execution from any origin that is contained in `T` (i.e. `T::Contains(origin)`) if the
+/// message begins with the instruction `UnpaidExecution`.
+///
+/// Use only for executions from trusted origin groups.
+///
+/// Messages beginning with origin-altering instructions such as `DescendOrigin` are rejected. To
+/// accept them, wrap this barrier in `WithComputedOrigin` so that `T` is checked against the
+/// computed origin and `UnpaidExecution` must follow the origin-altering instructions.
+pub struct AllowExplicitUnpaidExecutionFrom<T>(PhantomData<T>);
+impl<T: Contains<MultiLocation>> ShouldExecute for AllowExplicitUnpaidExecutionFrom<T> {
+	fn should_execute<Call>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<Call>],
+		max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowExplicitUnpaidExecutionFrom origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, max_weight, _weight_credit,
+		);
+		ensure!(T::contains(origin), ());
+		instructions.matcher().match_next_inst(|inst| match inst {
+			UnpaidExecution { weight_limit: Limited(m), .. } if m.all_gte(max_weight) => Ok(()),
+			UnpaidExecution { weight_limit: Unlimited, .. } => Ok(()),
+			_ => Err(()),
+		})?;
+		Ok(())
+	}
+}
+
+/// Allows a message only if it is from a system-level child parachain.
+pub struct IsChildSystemParachain<ParaId>(PhantomData<ParaId>);
+impl<ParaId: IsSystem + From<u32>> Contains<MultiLocation> for IsChildSystemParachain<ParaId> {
+	fn contains(l: &MultiLocation) -> bool {
+		matches!(
+			l.interior(),
+			Junctions::X1(Junction::Parachain(id))
+				if ParaId::from(*id).is_system() && l.parent_count() == 0,
+		)
+	}
+}
+
+/// Allows only messages if the generic `ResponseHandler` expects them via `expecting_response`.
+pub struct AllowKnownQueryResponses<ResponseHandler>(PhantomData<ResponseHandler>);
+impl<ResponseHandler: OnResponse> ShouldExecute for AllowKnownQueryResponses<ResponseHandler> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowKnownQueryResponses origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		instructions
+			.matcher()
+			.assert_remaining_insts(1)?
+			.match_next_inst(|inst| match inst {
+				QueryResponse { query_id, querier, .. }
+					if ResponseHandler::expecting_response(origin, *query_id, querier.as_ref()) =>
+					Ok(()),
+				_ => Err(()),
+			})?;
+		Ok(())
+	}
+}
+
+/// Type which knows about the queries we initiated and so expect responses to.
+pub trait QueryRegistry {
+	/// Returns `true` if a response to `query_id` is expected from `origin`.
+	fn expects(query_id: QueryId, origin: &MultiLocation) -> bool;
+}
+
+/// Rejects any message containing a `QueryResponse` instruction, including within nested programs,
+/// whose `query_id` is not expected from `origin` by `Registry`.
+///
+/// Filter barrier; see `AllOf`.
+pub struct AllowResponsesOnlyFromExpected<Registry>(PhantomData<Registry>);
+impl<Registry: QueryRegistry> ShouldExecute for AllowResponsesOnlyFromExpected<Registry> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowResponsesOnlyFromExpected origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		let walked = walk_instructions(instructions, &mut |inst| match inst {
+			QueryResponse { query_id, .. } if !Registry::expects(*query_id, origin) =>
+				ControlFlow::Break(()),
+			_ => ControlFlow::Continue(()),
+		});
+		ensure!(walked.is_continue(), ());
+		Ok(())
+	}
+}
+
+/// Type which knows about some XCM topics, e.g. those of messages we sent and expect replies to.
+pub trait TopicRegistry {
+	/// Returns `true` if `topic` is known.
+	fn is_known(topic: &[u8; 32]) -> bool;
+}
+
+/// Rejects any message which does not end with a `SetTopic` instruction whose topic is known to
+/// `Registry`.
+///
+/// Filter barrier; see `AllOf`.
+pub struct AllowKnownTopics<Registry>(PhantomData<Registry>);
+impl<Registry: TopicRegistry> ShouldExecute for AllowKnownTopics<Registry> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowKnownTopics origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		ensure!(
+			matches!(instructions.last(), Some(SetTopic(topic)) if Registry::is_known(topic)),
+			()
+		);
+		Ok(())
+	}
+}
+
+/// Allows execution from `origin` if it is just a straight `SubscribeVersion` or
+/// `UnsubscribeVersion` instruction.
+pub struct AllowSubscriptionsFrom<T>(PhantomData<T>);
+impl<T: Contains<MultiLocation>> ShouldExecute for AllowSubscriptionsFrom<T> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		log::trace!(
+			target: "xcm::barriers",
+			"AllowSubscriptionsFrom origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}",
+			origin, instructions, _max_weight, _weight_credit,
+		);
+		ensure!(T::contains(origin), ());
+		instructions
+			.matcher()
+			.assert_remaining_insts(1)?
+			.match_next_inst(|inst| match inst {
+				SubscribeVersion { .. } | UnsubscribeVersion => Ok(()),
+				_ => Err(()),
+			})?;
+		Ok(())
+	}
+}
+
+/// Evaluates the barrier `B` against a message as the executor would, e.g. to find out offline why
+/// a message captured from chain events was rejected.
+///
+/// `message_hash` is the hash under which the message was executed, from which the `XcmContext`
+/// is built just as the executor builds it. A captured message may be decoded with
+/// `decode_captured_message`.
+pub fn evaluate_barrier<B: ShouldExecute, Call>(
+	origin: &MultiLocation,
+	instructions: &mut [Instruction<Call>],
+	max_weight: Weight,
+	weight_credit: &mut Weight,
+	message_hash: XcmHash,
+) -> Result<(), ()> {
+	let context = XcmContext { origin: Some(*origin), message_hash, topic: None };
+	B::should_execute_with_context(origin, instructions, max_weight, weight_credit, &context)
+}
+
+/// Decodes a captured SCALE-encoded `(MultiLocation, Xcm<Call>)` pair of origin and message,
+/// observing the usual XCM decode depth limit and rejecting any trailing bytes.
+pub fn decode_captured_message<Call>(
+	mut encoded: &[u8],
+) -> Result<(MultiLocation, Xcm<Call>), parity_scale_codec::Error> {
+	<(MultiLocation, Xcm<Call>)>::decode_all_with_depth_limit(MAX_XCM_DECODE_DEPTH, &mut encoded)
+}
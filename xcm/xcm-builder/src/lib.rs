@@ -49,10 +49,21 @@ pub use asset_conversion::{ConvertedAbstractAssetId, ConvertedConcreteAssetId};
 
 mod barriers;
 pub use barriers::{
-	AllowExplicitUnpaidExecutionFrom, AllowKnownQueryResponses, AllowSubscriptionsFrom,
-	AllowTopLevelPaidExecutionFrom, AllowUnpaidExecutionFrom, IsChildSystemParachain,
-	TakeWeightCredit, WithComputedOrigin,
+	decode_captured_message, evaluate_barrier, AllOf, AllowDescendOriginUpTo,
+	AllowExplicitUnpaidExecutionFrom, AllowKnownQueryResponses, AllowKnownTopics,
+	AllowOriginsUnder, AllowResponsesOnlyFromExpected, AllowSubscriptionsFrom,
+	AllowTopLevelPaidExecutionFrom, AllowTransferDestinations, AllowUnpaidExecutionFrom,
+	BarrierMetrics, BarrierTimingSink, CanonicalInstructionOrder, DenyInstructions,
+	EnforceInstructionOrder, EnforceWeightLimit, ExecutionStats, ExecutionStatsProvider,
+	InstructionOrder, InstructionOriginPolicy, IsChildSystemParachain, LimitAssetCount,
+	LimitInstructionCount, LimitNestingDepth, LimitProofSize, LimitTransacts, MatchesInstruction,
+	PerInstructionOriginPolicy, QueryRegistry, RateLimitBarrier, RateLimitStore,
+	RequireMinimumBuyExecution, RequireSelfBeneficiaryRefund, RequireSetTopic,
+	RestrictInstructionsTo, ShouldExecuteAll, TakeWeightCredit, TightenNearCapacity, TopicPosition,
+	TopicRegistry, WithComputedOrigin, WithLogTarget, WithMetrics,
 };
+#[cfg(feature = "std")]
+pub use barriers::{CatchPanics, Timed};
 
 mod currency_adapter;
 pub use currency_adapter::CurrencyAdapter;
@@ -85,3 +96,8 @@ pub use universal_exports::{
 	HaulBlobError, HaulBlobExporter, NetworkExportTable, SovereignPaidRemoteExporter,
 	UnpaidLocalExporter, UnpaidRemoteExporter,
 };
+
+#[doc(hidden)]
+pub mod __private {
+	pub use xcm::latest::Instruction;
+}
@@ -276,3 +276,1584 @@ fn allow_paid_should_work() {
 	);
 	assert_eq!(r, Ok(()))
 }
+
+#[test]
+fn allow_descend_origin_up_to_should_work() {
+	type Barrier = AllowDescendOriginUpTo<AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>, 8>;
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+
+	let message_with_descends = |n: u32| {
+		let mut instructions: Vec<_> = (0..n).map(|i| DescendOrigin(Parachain(i).into())).collect();
+		instructions.push(TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() });
+		Xcm::<()>(instructions)
+	};
+
+	for (descends, expected) in [(7, Ok(())), (8, Ok(())), (9, Err(()))] {
+		let mut message = message_with_descends(descends);
+		let r = Barrier::should_execute(
+			&Parent.into(),
+			message.inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+		);
+		assert_eq!(r, expected, "unexpected result for {} descends", descends);
+	}
+}
+
+#[test]
+fn allow_descend_origin_up_to_counts_descends_after_universal_origin() {
+	type Barrier = AllowDescendOriginUpTo<AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>, 8>;
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+
+	let message_with_descends = |n: u32| {
+		let mut instructions = vec![UniversalOrigin(GlobalConsensus(Kusama))];
+		instructions.extend((0..n).map(|i| DescendOrigin(Parachain(i).into())));
+		instructions.push(TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() });
+		Xcm::<()>(instructions)
+	};
+
+	for (descends, expected) in [(8, Ok(())), (9, Err(()))] {
+		let mut message = message_with_descends(descends);
+		let r = Barrier::should_execute(
+			&Parent.into(),
+			message.inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+		);
+		assert_eq!(r, expected, "unexpected result for {} descends", descends);
+	}
+}
+
+#[test]
+fn allow_descend_origin_up_to_counts_junctions() {
+	type Barrier = AllowDescendOriginUpTo<AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>, 2>;
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+
+	let message_descending = |interior: Junctions| {
+		Xcm::<()>(vec![
+			DescendOrigin(interior),
+			TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() },
+		])
+	};
+
+	for (interior, expected) in [
+		(X2(Parachain(1), PalletInstance(5)), Ok(())),
+		(X3(Parachain(1), PalletInstance(5), GeneralIndex(1)), Err(())),
+		(
+			X8(
+				Parachain(1),
+				PalletInstance(5),
+				GeneralIndex(1),
+				GeneralIndex(2),
+				GeneralIndex(3),
+				GeneralIndex(4),
+				GeneralIndex(5),
+				GeneralIndex(6),
+			),
+			Err(()),
+		),
+	] {
+		let mut message = message_descending(interior);
+		let r = Barrier::should_execute(
+			&Parent.into(),
+			message.inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+		);
+		assert_eq!(r, expected, "unexpected result for {:?}", interior);
+	}
+}
+
+#[test]
+fn allow_descend_origin_up_to_passes_message_to_inner_barrier() {
+	AllowPaidFrom::set(vec![(Parent, Parachain(100)).into()]);
+
+	let mut message = Xcm::<()>(vec![
+		DescendOrigin(Parachain(100).into()),
+		WithdrawAsset((Parent, 100).into()),
+		BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+	]);
+
+	let r = AllowDescendOriginUpTo::<
+		WithComputedOrigin<
+			AllowTopLevelPaidExecutionFrom<IsInVec<AllowPaidFrom>>,
+			ExecutorUniversalLocation,
+			ConstU32<2>,
+		>,
+		1,
+	>::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(30, 30),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+	assert_eq!(
+		message.0[2],
+		BuyExecution {
+			fees: (Parent, 100).into(),
+			weight_limit: Limited(Weight::from_parts(30, 30)),
+		},
+	);
+}
+
+crate::match_instructions! {
+	pub type TransactOrErrorHandler = { Transact, SetErrorHandler };
+}
+
+#[test]
+fn deny_instructions_should_work() {
+	let transact = || Transact {
+		origin_kind: OriginKind::Native,
+		require_weight_at_most: Weight::from_parts(10, 10),
+		call: vec![].into(),
+	};
+
+	let mut allowed = Xcm::<()>(vec![
+		ClearOrigin,
+		SetAppendix(Xcm(vec![RefundSurplus])),
+		TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() },
+	]);
+	let r = DenyInstructions::<TransactOrErrorHandler>::should_execute(
+		&Parent.into(),
+		allowed.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut top_level = Xcm::<()>(vec![ClearOrigin, transact()]);
+	let r = DenyInstructions::<TransactOrErrorHandler>::should_execute(
+		&Parent.into(),
+		top_level.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut in_appendix = Xcm::<()>(vec![
+		SetAppendix(Xcm(vec![RefundSurplus, transact()])),
+		TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() },
+	]);
+	let r = DenyInstructions::<TransactOrErrorHandler>::should_execute(
+		&Parent.into(),
+		in_appendix.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn take_weight_credit_barrier_should_not_saturate() {
+	let mut message =
+		Xcm::<()>(vec![TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() }]);
+
+	// Over-credit leaves the remainder for later barriers.
+	let mut weight_credit = Weight::from_parts(15, 15);
+	let r = TakeWeightCredit::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut weight_credit,
+	);
+	assert_eq!(r, Ok(()));
+	assert_eq!(weight_credit, Weight::from_parts(5, 5));
+
+	// Insufficient credit in either component is rejected and the credit left untouched.
+	let r = TakeWeightCredit::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 1),
+		&mut weight_credit,
+	);
+	assert_eq!(r, Err(()));
+	assert_eq!(weight_credit, Weight::from_parts(5, 5));
+
+	refund_weight_credit(&mut weight_credit, Weight::from_parts(5, 5));
+	let r = TakeWeightCredit::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 1),
+		&mut weight_credit,
+	);
+	assert_eq!(r, Ok(()));
+	assert_eq!(weight_credit, Weight::from_parts(0, 9));
+}
+
+parameter_types! {
+	pub static CurrentBlock: u32 = 1;
+	pub static RateLimitCounts: BTreeMap<MultiLocation, (u32, u32)> = BTreeMap::new();
+}
+
+pub struct TestRateLimitStore;
+impl RateLimitStore for TestRateLimitStore {
+	fn current_block() -> u32 {
+		CurrentBlock::get()
+	}
+	fn get(origin: &MultiLocation) -> Option<(u32, u32)> {
+		RateLimitCounts::get().get(origin).cloned()
+	}
+	fn set(origin: &MultiLocation, block: u32, count: u32) {
+		RateLimitCounts::mutate(|c| c.insert(*origin, (block, count)));
+	}
+}
+
+#[test]
+fn rate_limit_barrier_should_work() {
+	type Barrier = RateLimitBarrier<TestRateLimitStore, 3>;
+	CurrentBlock::set(1);
+
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let mut execute = |origin: MultiLocation| {
+		Barrier::should_execute(
+			&origin,
+			message.inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+		)
+	};
+
+	// Origins descended from the same parachain share its limit.
+	assert_eq!(execute((Parent, Parachain(1)).into()), Ok(()));
+	assert_eq!(execute((Parent, Parachain(1), PalletInstance(1)).into()), Ok(()));
+	assert_eq!(
+		execute((Parent, Parachain(1), AccountIndex64 { network: None, index: 1 }).into()),
+		Ok(())
+	);
+	assert_eq!(execute((Parent, Parachain(1), PalletInstance(2)).into()), Err(()));
+	// Other origins have their own limit.
+	assert_eq!(execute((Parent, Parachain(2)).into()), Ok(()));
+	assert_eq!(execute(Parent.into()), Ok(()));
+
+	// The count is reset in the next block.
+	CurrentBlock::set(2);
+	assert_eq!(execute((Parent, Parachain(1)).into()), Ok(()));
+}
+
+parameter_types! {
+	pub static Rejections: Vec<MultiLocation> = vec![];
+}
+
+pub struct RecordRejections;
+impl BarrierMetrics for RecordRejections {
+	fn on_reject(origin: &MultiLocation) {
+		Rejections::mutate(|r| r.push(*origin));
+	}
+}
+
+#[test]
+fn with_metrics_should_report_rejections() {
+	type Barrier =
+		WithMetrics<AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>, RecordRejections>;
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+	Rejections::set(vec![]);
+
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+	assert_eq!(Rejections::get(), vec![]);
+
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+	assert_eq!(Rejections::get(), vec![Parachain(1).into()]);
+}
+
+#[test]
+fn limit_instruction_count_should_work() {
+	let mut at_limit =
+		Xcm::<()>(vec![ClearOrigin, SetAppendix(Xcm(vec![ClearOrigin; 5])), ClearOrigin]);
+	let r = LimitInstructionCount::<3>::should_execute(
+		&Parent.into(),
+		at_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut over_limit = Xcm::<()>(vec![ClearOrigin; 4]);
+	let r = LimitInstructionCount::<3>::should_execute(
+		&Parent.into(),
+		over_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn all_of_should_work() {
+	type Barrier = AllOf<(
+		TakeWeightCredit,
+		LimitInstructionCount<2>,
+		AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>,
+	)>;
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+
+	// All pass.
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let mut weight_credit = Weight::from_parts(15, 15);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut weight_credit,
+	);
+	assert_eq!(r, Ok(()));
+	assert_eq!(weight_credit, Weight::from_parts(5, 5));
+
+	// First fails.
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut weight_credit,
+	);
+	assert_eq!(r, Err(()));
+	assert_eq!(weight_credit, Weight::from_parts(5, 5));
+
+	// Last fails, and the credit taken by the first is given back.
+	let mut weight_credit = Weight::from_parts(15, 15);
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut weight_credit,
+	);
+	assert_eq!(r, Err(()));
+	assert_eq!(weight_credit, Weight::from_parts(15, 15));
+
+	// Composes with a plain barrier tuple.
+	let r = <(Barrier, AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>)>::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+}
+
+#[test]
+fn enforce_weight_limit_should_work() {
+	let message_with_limit = |weight_limit| {
+		Xcm::<()>(vec![
+			WithdrawAsset((Parent, 100).into()),
+			BuyExecution { fees: (Parent, 100).into(), weight_limit },
+			DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+		])
+	};
+
+	for (weight_limit, expected) in [
+		(Unlimited, Ok(())),
+		(Limited(Weight::from_parts(30, 30)), Ok(())),
+		(Limited(Weight::from_parts(40, 30)), Ok(())),
+		(Limited(Weight::from_parts(20, 30)), Err(())),
+		(Limited(Weight::from_parts(30, 20)), Err(())),
+	] {
+		let mut message = message_with_limit(weight_limit.clone());
+		let r = EnforceWeightLimit::should_execute(
+			&Parent.into(),
+			message.inner_mut(),
+			Weight::from_parts(30, 30),
+			&mut Weight::zero(),
+		);
+		assert_eq!(r, expected, "unexpected result for {:?}", weight_limit);
+	}
+
+	let mut no_limit = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+	]);
+	let r = EnforceWeightLimit::should_execute(
+		&Parent.into(),
+		no_limit.inner_mut(),
+		Weight::from_parts(30, 30),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+parameter_types! {
+	pub const BridgeLogTarget: &'static str = "xcm::bridge::barriers";
+}
+
+#[test]
+fn with_log_target_should_return_inner_result() {
+	type Barrier =
+		WithLogTarget<AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>, BridgeLogTarget>;
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn deny_instructions_should_find_deeply_nested_instructions() {
+	let mut message = Xcm::<()>(vec![
+		ClearOrigin,
+		SetAppendix(Xcm(vec![
+			RefundSurplus,
+			SetAppendix(Xcm(vec![
+				ClearOrigin,
+				Transact {
+					origin_kind: OriginKind::Native,
+					require_weight_at_most: Weight::from_parts(10, 10),
+					call: vec![].into(),
+				},
+			])),
+		])),
+	]);
+	let r = DenyInstructions::<TransactOrErrorHandler>::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+parameter_types! {
+	pub static KnownTopics: Vec<[u8; 32]> = vec![];
+}
+
+pub struct TestTopicRegistry;
+impl TopicRegistry for TestTopicRegistry {
+	fn is_known(topic: &[u8; 32]) -> bool {
+		KnownTopics::get().contains(topic)
+	}
+}
+
+#[test]
+fn allow_known_topics_should_work() {
+	KnownTopics::set(vec![[1; 32]]);
+
+	let mut known = Xcm::<()>(vec![ClearOrigin, SetTopic([1; 32])]);
+	let r = AllowKnownTopics::<TestTopicRegistry>::should_execute(
+		&Parent.into(),
+		known.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut unknown = Xcm::<()>(vec![ClearOrigin, SetTopic([2; 32])]);
+	let r = AllowKnownTopics::<TestTopicRegistry>::should_execute(
+		&Parent.into(),
+		unknown.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut not_trailing = Xcm::<()>(vec![SetTopic([1; 32]), ClearOrigin]);
+	let r = AllowKnownTopics::<TestTopicRegistry>::should_execute(
+		&Parent.into(),
+		not_trailing.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn allow_known_topics_should_not_allow_untrusted_origins() {
+	KnownTopics::set(vec![[1; 32]]);
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+
+	type Barrier = AllOf<(
+		AllowKnownTopics<TestTopicRegistry>,
+		AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>,
+	)>;
+
+	let mut message = Xcm::<()>(vec![ClearOrigin, SetTopic([1; 32])]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	// A known topic is not enough for an origin which no allow barrier lets through.
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn limit_transacts_should_work() {
+	let transact = || Transact {
+		origin_kind: OriginKind::Native,
+		require_weight_at_most: Weight::from_parts(10, 10),
+		call: vec![].into(),
+	};
+
+	let mut at_limit = Xcm::<()>(vec![transact(), ClearOrigin, transact()]);
+	let r = LimitTransacts::<2>::should_execute(
+		&Parent.into(),
+		at_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut over_limit = Xcm::<()>(vec![transact(), transact(), transact()]);
+	let r = LimitTransacts::<2>::should_execute(
+		&Parent.into(),
+		over_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut nested_over_limit =
+		Xcm::<()>(vec![transact(), transact(), SetAppendix(Xcm(vec![transact()]))]);
+	let r = LimitTransacts::<2>::should_execute(
+		&Parent.into(),
+		nested_over_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+crate::match_instructions! {
+	pub type TransactOnly = { Transact };
+}
+
+parameter_types! {
+	pub static TransactOrigins: Vec<MultiLocation> = vec![];
+}
+
+#[test]
+fn per_instruction_origin_policy_should_work() {
+	type Barrier =
+		PerInstructionOriginPolicy<RestrictInstructionsTo<TransactOnly, IsInVec<TransactOrigins>>>;
+	TransactOrigins::set(vec![Parent.into()]);
+
+	let mut transfer =
+		Xcm::<()>(vec![TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() }]);
+	let mut transact = Xcm::<()>(vec![
+		ClearOrigin,
+		SetAppendix(Xcm(vec![Transact {
+			origin_kind: OriginKind::Native,
+			require_weight_at_most: Weight::from_parts(10, 10),
+			call: vec![].into(),
+		}])),
+	]);
+
+	// Any origin may send instructions which are not restricted.
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		transfer.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		transact.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		transact.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+parameter_types! {
+	pub static CurrentExecutionStats: ExecutionStats = ExecutionStats::default();
+	pub static StrictAllowUnpaidFrom: Vec<MultiLocation> = vec![];
+	pub const CapacityThreshold: Weight = Weight::from_parts(100, 100);
+	pub const CapacityMaxMessages: u32 = 10;
+}
+
+pub struct TestExecutionStats;
+impl ExecutionStatsProvider for TestExecutionStats {
+	fn execution_stats() -> ExecutionStats {
+		CurrentExecutionStats::get()
+	}
+}
+
+#[test]
+fn tighten_near_capacity_should_work() {
+	type Barrier = TightenNearCapacity<
+		AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>,
+		AllowUnpaidExecutionFrom<IsInVec<StrictAllowUnpaidFrom>>,
+		TestExecutionStats,
+		CapacityThreshold,
+		CapacityMaxMessages,
+	>;
+	AllowUnpaidFrom::set(vec![Parent.into(), Parachain(1).into()]);
+	StrictAllowUnpaidFrom::set(vec![Parent.into()]);
+
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let mut execute = |origin: MultiLocation| {
+		Barrier::should_execute(
+			&origin,
+			message.inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+		)
+	};
+
+	CurrentExecutionStats::set(ExecutionStats {
+		messages_executed: 5,
+		weight_consumed: Weight::from_parts(50, 99),
+	});
+	assert_eq!(execute(Parent.into()), Ok(()));
+	assert_eq!(execute(Parachain(1).into()), Ok(()));
+
+	CurrentExecutionStats::set(ExecutionStats {
+		messages_executed: 9,
+		weight_consumed: Weight::from_parts(50, 100),
+	});
+	assert_eq!(execute(Parent.into()), Ok(()));
+	assert_eq!(execute(Parachain(1).into()), Err(()));
+
+	// Too many messages tightens the barrier even with plenty of weight left.
+	CurrentExecutionStats::set(ExecutionStats {
+		messages_executed: 10,
+		weight_consumed: Weight::from_parts(50, 99),
+	});
+	assert_eq!(execute(Parent.into()), Ok(()));
+	assert_eq!(execute(Parachain(1).into()), Err(()));
+}
+
+#[test]
+fn tighten_near_capacity_should_work_with_context() {
+	type Barrier = TightenNearCapacity<
+		AllOf<(DenySeenMessageHashes, AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>)>,
+		AllowUnpaidExecutionFrom<IsInVec<StrictAllowUnpaidFrom>>,
+		TestExecutionStats,
+		CapacityThreshold,
+		CapacityMaxMessages,
+	>;
+	AllowUnpaidFrom::set(vec![Parent.into(), Parachain(1).into()]);
+	StrictAllowUnpaidFrom::set(vec![Parent.into()]);
+	SeenMessageHashes::set(vec![[1; 32]]);
+
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let mut execute = |origin: MultiLocation, message_hash: XcmHash| {
+		Barrier::should_execute_with_context(
+			&origin,
+			message.inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+			&XcmContext { origin: Some(origin), message_hash, topic: None },
+		)
+	};
+
+	// Below capacity, the context reaches the relaxed barrier.
+	CurrentExecutionStats::set(ExecutionStats {
+		messages_executed: 5,
+		weight_consumed: Weight::from_parts(50, 99),
+	});
+	assert_eq!(execute(Parachain(1).into(), [2; 32]), Ok(()));
+	assert_eq!(execute(Parachain(1).into(), [1; 32]), Err(()));
+
+	CurrentExecutionStats::set(ExecutionStats {
+		messages_executed: 10,
+		weight_consumed: Weight::from_parts(50, 99),
+	});
+	assert_eq!(execute(Parent.into(), [1; 32]), Ok(()));
+	assert_eq!(execute(Parachain(1).into(), [2; 32]), Err(()));
+}
+
+#[test]
+fn allow_explicit_unpaid_with_computed_origin_should_work() {
+	type Barrier = WithComputedOrigin<
+		AllowExplicitUnpaidExecutionFrom<IsInVec<AllowExplicitUnpaidFrom>>,
+		ExecutorUniversalLocation,
+		ConstU32<2>,
+	>;
+	AllowExplicitUnpaidFrom::set(vec![(Parent, Parachain(1)).into()]);
+
+	let message = |declared: bool| {
+		let mut instructions = vec![DescendOrigin(Parachain(1).into())];
+		if declared {
+			instructions.push(UnpaidExecution { weight_limit: Unlimited, check_origin: None });
+		}
+		instructions.push(TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() });
+		Xcm::<()>(instructions)
+	};
+
+	// Trusted with declaration.
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message(true).inner_mut(),
+		Weight::from_parts(20, 20),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	// Trusted without declaration.
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message(false).inner_mut(),
+		Weight::from_parts(20, 20),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	// Untrusted with declaration.
+	let r = Barrier::should_execute(
+		&Parachain(2).into(),
+		message(true).inner_mut(),
+		Weight::from_parts(20, 20),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn limit_asset_count_should_work() {
+	let assets = |n: u128| -> MultiAssets {
+		(0..n)
+			.map(|i| (GeneralIndex(i), 100).into())
+			.collect::<Vec<MultiAsset>>()
+			.into()
+	};
+
+	let mut at_limit = Xcm::<()>(vec![
+		WithdrawAsset(assets(3)),
+		BuyExecution { fees: (GeneralIndex(0), 100).into(), weight_limit: Unlimited },
+		DepositAsset { assets: AllCounted(3).into(), beneficiary: Here.into() },
+	]);
+	let r = LimitAssetCount::<3>::should_execute(
+		&Parent.into(),
+		at_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut over_limit = Xcm::<()>(vec![
+		ReserveAssetDeposited(assets(4)),
+		BuyExecution { fees: (GeneralIndex(0), 100).into(), weight_limit: Unlimited },
+		DepositAsset { assets: AllCounted(4).into(), beneficiary: Here.into() },
+	]);
+	let r = LimitAssetCount::<3>::should_execute(
+		&Parent.into(),
+		over_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut nested_over_limit = Xcm::<()>(vec![
+		WithdrawAsset(assets(1)),
+		BuyExecution { fees: (GeneralIndex(0), 100).into(), weight_limit: Unlimited },
+		SetAppendix(Xcm(vec![TransferAsset { assets: assets(4), beneficiary: Here.into() }])),
+	]);
+	let r = LimitAssetCount::<3>::should_execute(
+		&Parent.into(),
+		nested_over_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let teleport = |filter: MultiAssetFilter| {
+		Xcm::<()>(vec![
+			WithdrawAsset(assets(1)),
+			BuyExecution { fees: (GeneralIndex(0), 100).into(), weight_limit: Unlimited },
+			InitiateTeleport { assets: filter, dest: Parent.into(), xcm: Xcm(vec![]) },
+		])
+	};
+	for (filter, expected) in [
+		(Wild(AllCounted(3)), Ok(())),
+		(Wild(AllCounted(4)), Err(())),
+		(Wild(AllCounted(u32::MAX)), Err(())),
+		(Wild(AllOfCounted { id: Concrete(Here.into()), fun: WildFungible, count: 4 }), Err(())),
+		(Definite(assets(3)), Ok(())),
+		(Definite(assets(4)), Err(())),
+	] {
+		let r = LimitAssetCount::<3>::should_execute(
+			&Parent.into(),
+			teleport(filter).inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+		);
+		assert_eq!(r, expected);
+	}
+
+	let response_info = QueryResponseInfo {
+		destination: Parent.into(),
+		query_id: 1,
+		max_weight: Weight::from_parts(10, 10),
+	};
+	for (mut message, expected) in [
+		(Xcm::<()>(vec![BurnAsset(assets(3))]), Ok(())),
+		(Xcm::<()>(vec![BurnAsset(assets(4))]), Err(())),
+		(Xcm::<()>(vec![ExpectAsset(assets(4))]), Err(())),
+		(
+			Xcm::<()>(vec![QueryResponse {
+				query_id: 1,
+				response: Response::Assets(assets(4)),
+				max_weight: Weight::from_parts(10, 10),
+				querier: None,
+			}]),
+			Err(()),
+		),
+		(
+			Xcm::<()>(vec![ReportHolding {
+				response_info: response_info.clone(),
+				assets: AllCounted(3).into(),
+			}]),
+			Ok(()),
+		),
+		(
+			Xcm::<()>(vec![ReportHolding {
+				response_info: response_info.clone(),
+				assets: AllCounted(4).into(),
+			}]),
+			Err(()),
+		),
+	] {
+		let r = LimitAssetCount::<3>::should_execute(
+			&Parent.into(),
+			message.inner_mut(),
+			Weight::from_parts(10, 10),
+			&mut Weight::zero(),
+		);
+		assert_eq!(r, expected);
+	}
+}
+
+#[test]
+fn catch_panics_should_work() {
+	struct Panics;
+	impl ShouldExecute for Panics {
+		fn should_execute<RuntimeCall>(
+			_origin: &MultiLocation,
+			_instructions: &mut [Instruction<RuntimeCall>],
+			_max_weight: Weight,
+			_weight_credit: &mut Weight,
+		) -> Result<(), ()> {
+			panic!("barrier bug")
+		}
+	}
+
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let r = CatchPanics::<Panics>::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let r = CatchPanics::<TakeWeightCredit>::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::from_parts(10, 10),
+	);
+	assert_eq!(r, Ok(()));
+}
+
+#[test]
+fn enforce_instruction_order_should_work() {
+	type Barrier = EnforceInstructionOrder<CanonicalInstructionOrder>;
+
+	let mut well_ordered = Xcm::<()>(vec![
+		DescendOrigin(X1(AccountIndex64 { network: None, index: 1 })),
+		ReserveAssetDeposited((Parent, 100).into()),
+		ClearOrigin,
+		BuyExecution {
+			fees: (Parent, 100).into(),
+			weight_limit: Limited(Weight::from_parts(30, 30)),
+		},
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+		ClearOrigin,
+	]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		well_ordered.inner_mut(),
+		Weight::from_parts(30, 30),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut misordered = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+		BuyExecution {
+			fees: (Parent, 100).into(),
+			weight_limit: Limited(Weight::from_parts(30, 30)),
+		},
+	]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		misordered.inner_mut(),
+		Weight::from_parts(30, 30),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	// As prefixed by `pallet_xcm::report_outcome`.
+	let mut leading_appendix = Xcm::<()>(vec![
+		SetAppendix(Xcm(vec![ReportError(QueryResponseInfo {
+			destination: Parent.into(),
+			query_id: 1,
+			max_weight: Weight::zero(),
+		})])),
+		WithdrawAsset((Parent, 100).into()),
+		BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+	]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		leading_appendix.inner_mut(),
+		Weight::from_parts(30, 30),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	// As required by `RequireSetTopic<Leading>`.
+	let mut leading_topic = Xcm::<()>(vec![
+		SetTopic([1; 32]),
+		WithdrawAsset((Parent, 100).into()),
+		BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+	]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		leading_topic.inner_mut(),
+		Weight::from_parts(30, 30),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+}
+
+parameter_types! {
+	pub static ExpectedQueries: Vec<(QueryId, MultiLocation)> = vec![];
+}
+
+pub struct TestQueryRegistry;
+impl QueryRegistry for TestQueryRegistry {
+	fn expects(query_id: QueryId, origin: &MultiLocation) -> bool {
+		ExpectedQueries::get().contains(&(query_id, *origin))
+	}
+}
+
+#[test]
+fn allow_responses_only_from_expected_should_work() {
+	type Barrier = AllowResponsesOnlyFromExpected<TestQueryRegistry>;
+	ExpectedQueries::set(vec![(42, Parachain(1).into())]);
+
+	let response = |query_id| {
+		Xcm::<()>(vec![QueryResponse {
+			query_id,
+			response: Response::Null,
+			max_weight: Weight::from_parts(10, 10),
+			querier: Some(Here.into()),
+		}])
+	};
+
+	let mut expected = response(42);
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		expected.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut unexpected = response(43);
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		unexpected.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut wrong_origin = response(42);
+	let r = Barrier::should_execute(
+		&Parachain(2).into(),
+		wrong_origin.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut nested = Xcm::<()>(vec![SetAppendix(response(43))]);
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		nested.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn limit_proof_size_should_work() {
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+
+	let r = LimitProofSize::<100>::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(1_000_000_000, 100),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let r = LimitProofSize::<100>::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(1, 101),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn evaluate_captured_message_should_work() {
+	type Barrier = (TakeWeightCredit, AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>);
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+
+	let origin: MultiLocation = Parent.into();
+	let message = Xcm::<()>(vec![
+		ClearOrigin,
+		TransferAsset { assets: (Parent, 100).into(), beneficiary: Here.into() },
+	]);
+	let encoded = (origin, message.clone()).encode();
+
+	let (decoded_origin, mut decoded) = decode_captured_message::<()>(&encoded).unwrap();
+	assert_eq!(decoded_origin, origin);
+	assert_eq!(decoded, message);
+
+	let r = evaluate_barrier::<Barrier, ()>(
+		&decoded_origin,
+		decoded.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+		[2; 32],
+	);
+	assert_eq!(r, Ok(()));
+
+	let r = evaluate_barrier::<Barrier, ()>(
+		&Parachain(1).into(),
+		decoded.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+		[2; 32],
+	);
+	assert_eq!(r, Err(()));
+
+	// The barrier is evaluated with the context the executor would give it.
+	SeenMessageHashes::set(vec![[1; 32]]);
+	type ContextBarrier =
+		AllOf<(DenySeenMessageHashes, AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>)>;
+	let r = evaluate_barrier::<ContextBarrier, ()>(
+		&decoded_origin,
+		decoded.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+		[2; 32],
+	);
+	assert_eq!(r, Ok(()));
+	let r = evaluate_barrier::<ContextBarrier, ()>(
+		&decoded_origin,
+		decoded.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+		[1; 32],
+	);
+	assert_eq!(r, Err(()));
+
+	let mut trailing = encoded;
+	trailing.push(0);
+	assert!(decode_captured_message::<()>(&trailing).is_err());
+}
+
+parameter_types! {
+	pub AllowedPrefix: MultiLocation = (Parent, Parachain(1), PalletInstance(5)).into();
+}
+
+#[test]
+fn allow_origins_under_should_work() {
+	type Barrier = AllowOriginsUnder<AllowedPrefix, ExecutorUniversalLocation, ConstU32<2>>;
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+
+	let inside: MultiLocation = (Parent, Parachain(1), PalletInstance(5), GeneralIndex(1)).into();
+	let r = Barrier::should_execute(
+		&inside,
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let outside: MultiLocation = (Parent, Parachain(1), PalletInstance(6)).into();
+	let r = Barrier::should_execute(
+		&outside,
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let above: MultiLocation = (Parent, Parachain(1)).into();
+	let r = Barrier::should_execute(
+		&above,
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut descends_into_prefix = Xcm::<()>(vec![
+		DescendOrigin(X1(PalletInstance(5))),
+		DescendOrigin(X1(GeneralIndex(1))),
+		ClearOrigin,
+	]);
+	let r = Barrier::should_execute(
+		&above,
+		descends_into_prefix.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	// A sender under the prefix which switches to a universal origin is no longer under it.
+	let mut universal_origin = Xcm::<()>(vec![
+		UniversalOrigin(GlobalConsensus(Kusama)),
+		DescendOrigin(X1(Parachain(1))),
+		ClearOrigin,
+	]);
+	let r = Barrier::should_execute(
+		&inside,
+		universal_origin.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn limit_nesting_depth_should_work() {
+	fn nested(depth: u8) -> Xcm<()> {
+		let mut xcm = Xcm::<()>(vec![ClearOrigin]);
+		for i in 0..depth {
+			xcm = if i % 2 == 0 {
+				Xcm(vec![SetAppendix(xcm)])
+			} else {
+				Xcm(vec![SetErrorHandler(xcm)])
+			};
+		}
+		xcm
+	}
+
+	let mut at_limit = nested(3);
+	let r = LimitNestingDepth::<3>::should_execute(
+		&Parent.into(),
+		at_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut over_limit = nested(4);
+	let r = LimitNestingDepth::<3>::should_execute(
+		&Parent.into(),
+		over_limit.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+parameter_types! {
+	pub static RequiredTopicPosition: TopicPosition = TopicPosition::Anywhere;
+}
+
+#[test]
+fn require_set_topic_should_work() {
+	type Barrier = RequireSetTopic<RequiredTopicPosition>;
+	let mut trailing = Xcm::<()>(vec![ClearOrigin, SetTopic([1; 32])]);
+	let mut without = Xcm::<()>(vec![ClearOrigin]);
+
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		trailing.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		without.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	RequiredTopicPosition::set(TopicPosition::Trailing);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		trailing.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	RequiredTopicPosition::set(TopicPosition::Leading);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		trailing.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+parameter_types! {
+	pub static TransferDestinations: Vec<MultiLocation> = vec![];
+}
+
+#[test]
+fn allow_transfer_destinations_should_work() {
+	type Barrier = AllowTransferDestinations<IsInVec<TransferDestinations>>;
+	TransferDestinations::set(vec![(Parent, Parachain(1000)).into()]);
+
+	let deposit_reserve = |dest: MultiLocation| DepositReserveAsset {
+		assets: AllCounted(1).into(),
+		dest,
+		xcm: Xcm(vec![]),
+	};
+
+	let mut allowed = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		deposit_reserve((Parent, Parachain(1000)).into()),
+	]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		allowed.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut disallowed = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		InitiateReserveWithdraw {
+			assets: AllCounted(1).into(),
+			reserve: (Parent, Parachain(2000)).into(),
+			xcm: Xcm(vec![]),
+		},
+	]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		disallowed.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut nested = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		SetErrorHandler(Xcm(vec![deposit_reserve((Parent, Parachain(2000)).into())])),
+	]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		nested.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+#[test]
+fn require_minimum_buy_execution_should_work() {
+	type Barrier = RequireMinimumBuyExecution<100, 10>;
+	let buy = |weight_limit| {
+		Xcm::<()>(vec![
+			WithdrawAsset((Parent, 100).into()),
+			BuyExecution { fees: (Parent, 100).into(), weight_limit },
+			DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+		])
+	};
+
+	let mut below_floor = buy(Limited(Weight::from_parts(99, 10)));
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		below_floor.inner_mut(),
+		Weight::from_parts(100, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut below_pov_floor = buy(Limited(Weight::from_parts(100, 9)));
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		below_pov_floor.inner_mut(),
+		Weight::from_parts(100, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut at_floor = buy(Limited(Weight::from_parts(100, 10)));
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		at_floor.inner_mut(),
+		Weight::from_parts(100, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut unlimited = buy(Unlimited);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		unlimited.inner_mut(),
+		Weight::from_parts(100, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+}
+
+parameter_types! {
+	pub static TimingSamples: u32 = 0;
+}
+
+pub struct CountTimingSamples;
+impl BarrierTimingSink for CountTimingSamples {
+	fn record(_duration: core::time::Duration) {
+		TimingSamples::mutate(|n| *n += 1);
+	}
+}
+
+#[test]
+fn timed_should_record_a_sample_per_evaluation() {
+	type Barrier = Timed<AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>, CountTimingSamples>;
+	AllowUnpaidFrom::set(vec![Parent.into()]);
+	TimingSamples::set(0);
+
+	let mut message = Xcm::<()>(vec![ClearOrigin]);
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+	assert_eq!(TimingSamples::get(), 1);
+
+	let r = Barrier::should_execute(
+		&Parachain(1).into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+	assert_eq!(TimingSamples::get(), 2);
+}
+
+#[test]
+fn require_self_beneficiary_refund_should_work() {
+	type Barrier = RequireSelfBeneficiaryRefund<ExecutorUniversalLocation, ConstU32<2>>;
+	let origin: MultiLocation = (Parent, Parachain(1)).into();
+	let with_refund_to = |beneficiary: MultiLocation| {
+		Xcm::<()>(vec![
+			WithdrawAsset((Parent, 100).into()),
+			BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+			DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+			SetAppendix(Xcm(vec![
+				RefundSurplus,
+				DepositAsset { assets: AllCounted(1).into(), beneficiary },
+			])),
+		])
+	};
+
+	let mut matching = with_refund_to(origin);
+	let r = Barrier::should_execute(
+		&origin,
+		matching.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	let mut mismatching = with_refund_to((Parent, Parachain(2)).into());
+	let r = Barrier::should_execute(
+		&origin,
+		mismatching.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	let mut no_refund = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+	]);
+	let r = Barrier::should_execute(
+		&origin,
+		no_refund.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Ok(()));
+
+	// Appendices run after the program which sets them, so after its `RefundSurplus`.
+	let mut refund_in_outer_program = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+		SetAppendix(Xcm(vec![DepositAsset {
+			assets: AllCounted(1).into(),
+			beneficiary: (Parent, Parachain(2)).into(),
+		}])),
+		RefundSurplus,
+	]);
+	let r = Barrier::should_execute(
+		&origin,
+		refund_in_outer_program.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	// The same holds for a `RefundSurplus` within another nested program of the outer program.
+	let mut refund_in_sibling_program = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 100).into()),
+		BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+		SetErrorHandler(Xcm(vec![RefundSurplus])),
+		SetAppendix(Xcm(vec![DepositAsset {
+			assets: AllCounted(1).into(),
+			beneficiary: (Parent, Parachain(2)).into(),
+		}])),
+	]);
+	let r = Barrier::should_execute(
+		&origin,
+		refund_in_sibling_program.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+
+	// The beneficiary must be the origin computed like `WithComputedOrigin` does.
+	let mut universal_origin = Xcm::<()>(vec![
+		UniversalOrigin(GlobalConsensus(Kusama)),
+		WithdrawAsset((Parent, 100).into()),
+		BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+		RefundSurplus,
+		DepositAsset { assets: AllCounted(1).into(), beneficiary: origin },
+	]);
+	let r = Barrier::should_execute(
+		&origin,
+		universal_origin.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
+
+parameter_types! {
+	pub static SeenMessageHashes: Vec<XcmHash> = vec![];
+}
+
+/// Rejects any message whose hash was already seen, and any message without a context.
+pub struct DenySeenMessageHashes;
+impl ShouldExecute for DenySeenMessageHashes {
+	fn should_execute<RuntimeCall>(
+		_origin: &MultiLocation,
+		_instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		Err(())
+	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		_origin: &MultiLocation,
+		_instructions: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		ensure!(!SeenMessageHashes::get().contains(&context.message_hash), ());
+		Ok(())
+	}
+}
+
+#[test]
+fn derivative_barriers_should_forward_context() {
+	type Barrier = (
+		TakeWeightCredit,
+		WithComputedOrigin<
+			AllOf<(DenySeenMessageHashes, AllowUnpaidExecutionFrom<IsInVec<AllowUnpaidFrom>>)>,
+			ExecutorUniversalLocation,
+			ConstU32<2>,
+		>,
+	);
+	AllowUnpaidFrom::set(vec![(Parent, Parachain(1)).into()]);
+	SeenMessageHashes::set(vec![[1; 32]]);
+
+	let mut message = Xcm::<()>(vec![DescendOrigin(Parachain(1).into()), ClearOrigin]);
+	let r = Barrier::should_execute_with_context(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+		&XcmContext::with_message_hash([2; 32]),
+	);
+	assert_eq!(r, Ok(()));
+
+	let r = Barrier::should_execute_with_context(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+		&XcmContext::with_message_hash([1; 32]),
+	);
+	assert_eq!(r, Err(()));
+
+	let r = Barrier::should_execute(
+		&Parent.into(),
+		message.inner_mut(),
+		Weight::from_parts(10, 10),
+		&mut Weight::zero(),
+	);
+	assert_eq!(r, Err(()));
+}
@@ -204,11 +204,13 @@ impl<Config: config::Config> ExecuteXcm<Config::RuntimeCall> for XcmExecutor<Con
 			message,
 			weight_credit,
 		);
-		if let Err(e) = Config::Barrier::should_execute(
+		let context = XcmContext { origin: Some(origin), message_hash, topic: None };
+		if let Err(e) = Config::Barrier::should_execute_with_context(
 			&origin,
 			message.inner_mut(),
 			xcm_weight,
 			&mut weight_credit,
+			&context,
 		) {
 			log::trace!(
 				target: "xcm::execute_xcm_in_credit",
@@ -40,7 +40,9 @@ pub use token_matching::{
 mod on_response;
 pub use on_response::{OnResponse, VersionChangeNotifier};
 mod should_execute;
-pub use should_execute::ShouldExecute;
+pub use should_execute::{
+	checked_add_credit, refund_weight_credit, walk_instructions, ShouldExecute,
+};
 mod transact_asset;
 pub use transact_asset::TransactAsset;
 mod weight;
@@ -48,10 +50,11 @@ pub use weight::{WeightBounds, WeightTrader};
 
 pub mod prelude {
 	pub use super::{
-		export_xcm, validate_export, AssetExchange, AssetLock, ClaimAssets, Convert, ConvertOrigin,
-		Decoded, DropAssets, Enact, Encoded, Error, ExportXcm, FeeManager, FeeReason, Identity,
-		JustTry, LockError, MatchesFungible, MatchesFungibles, MatchesNonFungible,
-		MatchesNonFungibles, OnResponse, ShouldExecute, TransactAsset, VersionChangeNotifier,
-		WeightBounds, WeightTrader, WithOriginFilter,
+		checked_add_credit, export_xcm, refund_weight_credit, validate_export, walk_instructions,
+		AssetExchange, AssetLock, ClaimAssets, Convert, ConvertOrigin, Decoded, DropAssets, Enact,
+		Encoded, Error, ExportXcm, FeeManager, FeeReason, Identity, JustTry, LockError,
+		MatchesFungible, MatchesFungibles, MatchesNonFungible, MatchesNonFungibles, OnResponse,
+		ShouldExecute, TransactAsset, VersionChangeNotifier, WeightBounds, WeightTrader,
+		WithOriginFilter,
 	};
 }
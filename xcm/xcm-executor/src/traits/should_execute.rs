@@ -14,8 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
-use sp_std::result::Result;
-use xcm::latest::{Instruction, MultiLocation, Weight};
+use sp_std::{ops::ControlFlow, result::Result};
+use xcm::latest::{Instruction, MultiLocation, Weight, XcmContext};
 
 /// Trait to determine whether the execution engine should actually execute a given XCM.
 ///
@@ -30,12 +30,39 @@ pub trait ShouldExecute {
 	/// - `weight_credit`: The pre-established amount of weight that the system has determined this
 	///   message may utilize in its execution. Typically non-zero only because of prior fee
 	///   payment, but could in principle be due to other factors.
+	///
+	/// Implementations which take from `weight_credit` must never let it saturate: if the credit
+	/// is insufficient they should leave it untouched and return `Err`. Credit which was taken
+	/// but turns out not to be needed may be given back with `refund_weight_credit`. Any other
+	/// credit, e.g. for fees paid, must be added with `checked_add_credit`, rejecting the message
+	/// if it would overflow.
 	fn should_execute<RuntimeCall>(
 		origin: &MultiLocation,
 		instructions: &mut [Instruction<RuntimeCall>],
 		max_weight: Weight,
 		weight_credit: &mut Weight,
 	) -> Result<(), ()>;
+
+	/// Returns `true` if the given `message` may be executed, with access to the `XcmContext` of
+	/// the message. This is what the executor calls.
+	///
+	/// Parameters are as for `should_execute`, with the addition of:
+	/// - `context`: The context of the message. Its `topic` is always `None`, since no instruction
+	///   has been executed yet.
+	///
+	/// By default the context is ignored. Barriers which need it, e.g. to reject a message hash
+	/// which was already seen, should override this, and have `should_execute` decide
+	/// conservatively for when no context is available. Barriers which wrap others should forward
+	/// the context to them.
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		_context: &XcmContext,
+	) -> Result<(), ()> {
+		Self::should_execute(origin, instructions, max_weight, weight_credit)
+	}
 }
 
 #[impl_trait_for_tuples::impl_for_tuples(30)]
@@ -62,4 +89,238 @@ impl ShouldExecute for Tuple {
 		);
 		Err(())
 	}
+
+	fn should_execute_with_context<RuntimeCall>(
+		origin: &MultiLocation,
+		instructions: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+		context: &XcmContext,
+	) -> Result<(), ()> {
+		for_tuples!( #(
+			match Tuple::should_execute_with_context(
+				origin, instructions, max_weight, weight_credit, context,
+			) {
+				Ok(()) => return Ok(()),
+				_ => (),
+			}
+		)* );
+		log::trace!(
+			target: "xcm::should_execute",
+			"did not pass barrier: origin: {:?}, instructions: {:?}, max_weight: {:?}, weight_credit: {:?}, context: {:?}",
+			origin,
+			instructions,
+			max_weight,
+			weight_credit,
+			context,
+		);
+		Err(())
+	}
+}
+
+/// Give `amount` of weight back to `weight_credit`, e.g. when a barrier has taken more than it
+/// ends up requiring. Saturates rather than overflowing, which cannot happen when giving back no
+/// more than was taken.
+pub fn refund_weight_credit(weight_credit: &mut Weight, amount: Weight) {
+	*weight_credit = weight_credit.saturating_add(amount);
+}
+
+/// Add `amount` of weight to `weight_credit`, failing and leaving it untouched if either component
+/// would overflow.
+pub fn checked_add_credit(weight_credit: &mut Weight, amount: Weight) -> Result<(), ()> {
+	*weight_credit = weight_credit.checked_add(&amount).ok_or(())?;
+	Ok(())
+}
+
+/// Call `f` with each of `instructions` in order, including those of any programs nested within
+/// `SetAppendix` or `SetErrorHandler`, which are visited straight after the instruction containing
+/// them. Stops as soon as `f` returns `ControlFlow::Break`, and returns what it broke with.
+///
+/// Useful for barriers which filter on the content of a message, since a filter which only looks at
+/// the top-level instructions could be bypassed by nesting.
+pub fn walk_instructions<Call>(
+	instructions: &[Instruction<Call>],
+	f: &mut impl FnMut(&Instruction<Call>) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+	for instruction in instructions {
+		f(instruction)?;
+		match instruction {
+			Instruction::SetAppendix(xcm) | Instruction::SetErrorHandler(xcm) =>
+				walk_instructions(&xcm.0, f)?,
+			_ => (),
+		}
+	}
+	ControlFlow::Continue(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use xcm::latest::{Instruction::ClearOrigin, Junctions::Here};
+
+	struct AllowAll;
+	impl ShouldExecute for AllowAll {
+		fn should_execute<RuntimeCall>(
+			_origin: &MultiLocation,
+			_instructions: &mut [Instruction<RuntimeCall>],
+			_max_weight: Weight,
+			_weight_credit: &mut Weight,
+		) -> Result<(), ()> {
+			Ok(())
+		}
+	}
+
+	struct DenyHash;
+	impl ShouldExecute for DenyHash {
+		fn should_execute<RuntimeCall>(
+			_origin: &MultiLocation,
+			_instructions: &mut [Instruction<RuntimeCall>],
+			_max_weight: Weight,
+			_weight_credit: &mut Weight,
+		) -> Result<(), ()> {
+			Err(())
+		}
+
+		fn should_execute_with_context<RuntimeCall>(
+			_origin: &MultiLocation,
+			_instructions: &mut [Instruction<RuntimeCall>],
+			_max_weight: Weight,
+			_weight_credit: &mut Weight,
+			context: &XcmContext,
+		) -> Result<(), ()> {
+			if context.message_hash == [1; 32] {
+				Err(())
+			} else {
+				Ok(())
+			}
+		}
+	}
+
+	#[test]
+	fn should_execute_with_context_should_work() {
+		let origin = MultiLocation::new(1, Here);
+		let mut instructions: Vec<Instruction<()>> = vec![ClearOrigin];
+		let context = XcmContext::with_message_hash([1; 32]);
+
+		let r = AllowAll::should_execute_with_context(
+			&origin,
+			&mut instructions,
+			Weight::zero(),
+			&mut Weight::zero(),
+			&context,
+		);
+		assert_eq!(r, Ok(()));
+		let r = <(AllowAll, AllowAll)>::should_execute_with_context(
+			&origin,
+			&mut instructions,
+			Weight::zero(),
+			&mut Weight::zero(),
+			&context,
+		);
+		assert_eq!(r, Ok(()));
+
+		let r = DenyHash::should_execute_with_context(
+			&origin,
+			&mut instructions,
+			Weight::zero(),
+			&mut Weight::zero(),
+			&context,
+		);
+		assert_eq!(r, Err(()));
+		let r = DenyHash::should_execute_with_context(
+			&origin,
+			&mut instructions,
+			Weight::zero(),
+			&mut Weight::zero(),
+			&XcmContext::with_message_hash([2; 32]),
+		);
+		assert_eq!(r, Ok(()));
+
+		let r = <(DenyHash,)>::should_execute_with_context(
+			&origin,
+			&mut instructions,
+			Weight::zero(),
+			&mut Weight::zero(),
+			&context,
+		);
+		assert_eq!(r, Err(()));
+		let r = <(DenyHash,)>::should_execute_with_context(
+			&origin,
+			&mut instructions,
+			Weight::zero(),
+			&mut Weight::zero(),
+			&XcmContext::with_message_hash([2; 32]),
+		);
+		assert_eq!(r, Ok(()));
+	}
+
+	#[test]
+	fn checked_add_credit_should_work() {
+		let mut credit = Weight::from_parts(10, 20);
+		assert_eq!(checked_add_credit(&mut credit, Weight::from_parts(5, 5)), Ok(()));
+		assert_eq!(credit, Weight::from_parts(15, 25));
+
+		assert_eq!(checked_add_credit(&mut credit, Weight::from_parts(u64::MAX, 0)), Err(()));
+		assert_eq!(credit, Weight::from_parts(15, 25));
+
+		assert_eq!(checked_add_credit(&mut credit, Weight::from_parts(0, u64::MAX)), Err(()));
+		assert_eq!(credit, Weight::from_parts(15, 25));
+	}
+
+	#[test]
+	fn walk_instructions_should_visit_nested_programs() {
+		use xcm::latest::{Instruction::*, Xcm};
+		let instructions: Vec<Instruction<()>> = vec![
+			ClearOrigin,
+			SetAppendix(Xcm(vec![SetErrorHandler(Xcm(vec![RefundSurplus])), ClearTopic])),
+			ClearError,
+		];
+		let mut visited = vec![];
+		let r = walk_instructions(&instructions, &mut |i| {
+			visited.push(i.clone());
+			ControlFlow::Continue(())
+		});
+		assert_eq!(r, ControlFlow::Continue(()));
+		assert_eq!(
+			visited,
+			vec![
+				ClearOrigin,
+				instructions[1].clone(),
+				SetErrorHandler(Xcm(vec![RefundSurplus])),
+				RefundSurplus,
+				ClearTopic,
+				ClearError,
+			],
+		);
+
+		// Breaking from within a nested program stops the whole walk.
+		let mut visited = vec![];
+		let r = walk_instructions(&instructions, &mut |i| {
+			visited.push(i.clone());
+			match i {
+				RefundSurplus => ControlFlow::Break(()),
+				_ => ControlFlow::Continue(()),
+			}
+		});
+		assert_eq!(r, ControlFlow::Break(()));
+		assert_eq!(
+			visited,
+			vec![
+				ClearOrigin,
+				instructions[1].clone(),
+				SetErrorHandler(Xcm(vec![RefundSurplus])),
+				RefundSurplus,
+			],
+		);
+	}
+
+	#[test]
+	fn refund_weight_credit_should_work() {
+		let mut credit = Weight::from_parts(10, 20);
+		refund_weight_credit(&mut credit, Weight::from_parts(5, 5));
+		assert_eq!(credit, Weight::from_parts(15, 25));
+
+		refund_weight_credit(&mut credit, Weight::from_parts(u64::MAX, 0));
+		assert_eq!(credit, Weight::from_parts(u64::MAX, 25));
+	}
 }